@@ -0,0 +1,2205 @@
+//! Commands for the ~/.madola/gen_cpp workspace: listing, content,
+//! templates, trash/undo, zip export/import, and the clear handshake.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::Ordering;
+use std::sync::{Arc, Mutex};
+
+use tauri::Manager;
+
+use crate::commands::files::{
+    apply_save_style, check_file_size, count_lines_chars, detect_line_ending, hash_file_streaming,
+    strip_bom,
+};
+use crate::commands::{with_timeout, CancelFlags, CANCELLED_MSG};
+use crate::events::{emit_event, AppEvent};
+use crate::paths::{
+    madola_base, projects_path, resolve_existing_path, resolve_external, resolve_in_gen_cpp,
+    validate_cpp_filename, validate_module_name, validate_relative_cpp_path,
+};
+use crate::types::{
+    load_settings, natural_cmp, save_settings, sort_key_cmp, DeleteResult, DiffLine, ExtStat,
+    FileContentResult, FileInfo, FileListResult, ImportedFile, OutlineItem, Project,
+    ProgressPayload, SortKey, TemplateInfo,
+};
+
+// Single-use confirmation token for clear_gen_cpp, so one accidental call
+// can never wipe the directory
+#[derive(Default)]
+pub struct ClearTokens(Mutex<Option<(String, std::time::Instant)>>);
+
+const CLEAR_TOKEN_TTL_SECS: u64 = 60;
+
+// First step of the clear handshake: hand out a short-lived token
+#[tauri::command]
+pub fn request_clear_token(tokens: tauri::State<'_, ClearTokens>) -> String {
+    let token = uuid::Uuid::new_v4().to_string();
+    *tokens.0.lock().unwrap() = Some((token.clone(), std::time::Instant::now()));
+    token
+}
+
+// Shared body of clear_gen_cpp: collect what would go, and delete it
+// unless this is a dry run. Matches the same extension set the scan
+// lists, so managed .cpp.gz files are cleared alongside plain ones.
+fn clear_gen_cpp_in(
+    gen_cpp_dir: &Path,
+    extensions: &[String],
+    delete_subdirs: bool,
+    dry_run: bool,
+) -> DeleteResult {
+    let mut deleted = Vec::new();
+    if let Ok(entries) = fs::read_dir(gen_cpp_dir) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                if delete_subdirs {
+                    deleted.push(path.to_string_lossy().to_string());
+                    if !dry_run {
+                        if let Err(e) = fs::remove_dir_all(&path) {
+                            println!("[Rust] ERROR removing directory {:?}: {}", path, e);
+                        }
+                    }
+                }
+            } else if entry
+                .file_name()
+                .to_str()
+                .map(|name| managed_cpp_name(name, extensions))
+                .unwrap_or(false)
+            {
+                deleted.push(path.to_string_lossy().to_string());
+                if !dry_run {
+                    if let Err(e) = fs::remove_file(&path) {
+                        return DeleteResult {
+                            success: false,
+                            dry_run,
+                            deleted,
+                            error: Some(format!("Failed to delete {:?}: {}", path, e)),
+                        };
+                    }
+                }
+            }
+        }
+    }
+    DeleteResult {
+        success: true,
+        dry_run,
+        deleted,
+        error: None,
+    }
+}
+
+// Second step: delete all top-level .cpp files in gen_cpp, but only with a
+// fresh token from request_clear_token. Subdirectories survive unless
+// delete_subdirs is set. With dry_run the affected paths are returned
+// without touching the filesystem (and no token is consumed), so the UI
+// can show the precise impact before the real call.
+#[tauri::command]
+pub async fn clear_gen_cpp(
+    confirm_token: String,
+    delete_subdirs: Option<bool>,
+    dry_run: Option<bool>,
+    tokens: tauri::State<'_, ClearTokens>,
+) -> Result<DeleteResult, String> {
+    println!("[Rust] clear_gen_cpp called");
+    let dry_run = dry_run.unwrap_or(false);
+
+    let fail = |error: String| DeleteResult {
+        success: false,
+        dry_run,
+        deleted: vec![],
+        error: Some(error),
+    };
+
+    if !dry_run {
+        // Tokens are single-use: taken here whether or not they match
+        let stored = tokens.0.lock().unwrap().take();
+        let valid = matches!(
+            &stored,
+            Some((token, issued))
+                if *token == confirm_token
+                    && issued.elapsed().as_secs() <= CLEAR_TOKEN_TTL_SECS
+        );
+        if !valid {
+            return Ok(fail("Invalid or expired confirmation token".to_string()));
+        }
+    }
+
+    let gen_cpp_dir = match madola_base() {
+        Ok(base) => base.join("gen_cpp"),
+        Err(e) => return Ok(fail(e)),
+    };
+
+    // The deletion pass can touch hundreds of files; it runs on the
+    // blocking pool under the configured timeout, not on the main thread
+    with_timeout(move || {
+        let extensions = load_settings().cpp_extensions;
+        clear_gen_cpp_in(
+            &gen_cpp_dir,
+            &extensions,
+            delete_subdirs.unwrap_or(false),
+            dry_run,
+        )
+    })
+    .await
+}
+
+// Per-file advisory locks so concurrent operations on the same gen_cpp file
+// serialize instead of clobbering each other. This is process-local only --
+// it does not protect against another process (no cross-process flock).
+// Arc inside so a clone can travel into a blocking task.
+#[derive(Default, Clone)]
+pub struct FileLocks(Arc<Mutex<HashMap<String, Arc<Mutex<()>>>>>);
+
+impl FileLocks {
+    fn lock_for(&self, filename: &str) -> Arc<Mutex<()>> {
+        let mut map = self.0.lock().unwrap();
+        map.entry(filename.to_string())
+            .or_insert_with(|| Arc::new(Mutex::new(())))
+            .clone()
+    }
+}
+
+// One undoable destructive operation: where the old content went and the
+// gen_cpp name it belonged to
+struct TrashEntry {
+    filename: String,
+    trash_path: PathBuf,
+}
+
+// Per-session log of trashed file contents, newest last. Deletes and
+// overwriting saves push here; undo_last_operation pops. Arc inside so a
+// clone can travel into a blocking task.
+#[derive(Default, Clone)]
+pub struct TrashHistory(Arc<Mutex<Vec<TrashEntry>>>);
+
+const TRASH_HISTORY_LIMIT: usize = 10;
+
+// Move the current content of `path` into the trash directory and record it
+// for undo. A missing file is fine: there is nothing to preserve. History
+// beyond the limit is pruned oldest-first, trash file included.
+fn trash_existing_file(
+    history: &TrashHistory,
+    trash_dir: &Path,
+    filename: &str,
+    path: &Path,
+) -> Result<(), String> {
+    if !path.exists() {
+        return Ok(());
+    }
+    fs::create_dir_all(trash_dir)
+        .map_err(|e| format!("Failed to create directory: {}", e))?;
+    let trash_path = trash_dir.join(uuid::Uuid::new_v4().to_string());
+    fs::rename(path, &trash_path)
+        .map_err(|e| format!("Failed to move file to trash: {}", e))?;
+
+    let mut entries = history.0.lock().unwrap();
+    entries.push(TrashEntry {
+        filename: filename.to_string(),
+        trash_path,
+    });
+    if entries.len() > TRASH_HISTORY_LIMIT {
+        let oldest = entries.remove(0);
+        let _ = fs::remove_file(&oldest.trash_path);
+    }
+    Ok(())
+}
+
+// Where a trashed file should be restored to. If the original name has been
+// taken again in the meantime, fall back to a suffixed name rather than
+// clobbering the newer file.
+fn restore_target(gen_cpp_dir: &Path, filename: &str) -> PathBuf {
+    let original = gen_cpp_dir.join(filename);
+    if !original.exists() {
+        return original;
+    }
+    let (stem, ext) = if let Some(stem) = filename.strip_suffix(".cpp.gz") {
+        (stem, ".cpp.gz")
+    } else if let Some(stem) = filename.strip_suffix(".cpp") {
+        (stem, ".cpp")
+    } else {
+        (filename, "")
+    };
+    let mut n = 1;
+    loop {
+        let candidate = gen_cpp_dir.join(format!("{}.restored-{}{}", stem, n, ext));
+        if !candidate.exists() {
+            return candidate;
+        }
+        n += 1;
+    }
+}
+
+// File browser: SHA-256 of a gen_cpp file, for external-change detection
+#[tauri::command]
+pub async fn hash_cpp_file(filename: String) -> Result<String, String> {
+    println!("[Rust] hash_cpp_file called: {}", filename);
+    validate_cpp_filename(&filename)?;
+
+    let file_path = madola_base()?.join("gen_cpp").join(&filename);
+    if !file_path.exists() {
+        return Err("File not found".to_string());
+    }
+    with_timeout(move || hash_file_streaming(&file_path)).await?
+}
+
+// Lowercased extension of a filename, if it has one
+fn file_extension(file_name: &str) -> Option<String> {
+    Path::new(file_name)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_lowercase())
+}
+
+// For a gzip-compressed name like foo.cpp.gz, the inner extension ("cpp")
+// if it matches the configured list
+fn compressed_extension(file_name: &str, extensions: &[String]) -> Option<String> {
+    let stem = file_name
+        .strip_suffix(".gz")
+        .or_else(|| file_name.strip_suffix(".GZ"))?;
+    file_extension(stem).filter(|ext| extensions.iter().any(|e| e.eq_ignore_ascii_case(ext)))
+}
+
+// Whether a name is one the bulk commands manage: a configured extension,
+// plain or inside a .gz wrapper -- the same set the scan lists
+fn managed_cpp_name(file_name: &str, extensions: &[String]) -> bool {
+    match file_extension(file_name) {
+        Some(ext) if ext == "gz" => compressed_extension(file_name, extensions).is_some(),
+        Some(ext) => extensions.iter().any(|e| e.eq_ignore_ascii_case(&ext)),
+        None => false,
+    }
+}
+
+// Deepest nesting the recursive scan will follow, to avoid pathological trees
+const MAX_SCAN_DEPTH: u32 = 16;
+
+// Walk one directory level, recursing into subdirectories when asked.
+// Errors reading the root bubble up; unreadable subdirectories are skipped.
+#[allow(clippy::too_many_arguments)]
+fn collect_cpp_files(
+    dir: &Path,
+    prefix: &Path,
+    depth: u32,
+    recursive: bool,
+    with_hash: bool,
+    extensions: &[String],
+    files: &mut Vec<FileInfo>,
+) -> Result<(), String> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if depth == 0 => return Err(format!("Failed to read directory: {}", e)),
+        Err(e) => {
+            println!("[Rust] Skipping unreadable directory {:?}: {}", dir, e);
+            return Ok(());
+        }
+    };
+
+    for entry in entries.flatten() {
+        if let Ok(file_name) = entry.file_name().into_string() {
+            if entry.file_type().map(|t| t.is_dir()).unwrap_or(false) {
+                if recursive && depth < MAX_SCAN_DEPTH {
+                    collect_cpp_files(
+                        &entry.path(),
+                        &prefix.join(&file_name),
+                        depth + 1,
+                        recursive,
+                        with_hash,
+                        extensions,
+                        files,
+                    )?;
+                }
+                continue;
+            }
+            let (extension, compressed) = match file_extension(&file_name) {
+                Some(ext) if extensions.iter().any(|e| e.eq_ignore_ascii_case(&ext)) => {
+                    (ext, false)
+                }
+                Some(ext) if ext == "gz" => match compressed_extension(&file_name, extensions) {
+                    Some(inner) => (inner, true),
+                    None => continue,
+                },
+                _ => continue,
+            };
+            if let Ok(metadata) = entry.metadata() {
+                if let Ok(modified) = metadata.modified() {
+                    let modified_str = format!("{:?}", modified);
+                    println!("[Rust] Found C++ file: {} ({} bytes)", file_name, metadata.len());
+                    let hash = if with_hash {
+                        hash_file_streaming(&entry.path()).ok()
+                    } else {
+                        None
+                    };
+                    let relative_path = prefix
+                        .join(&file_name)
+                        .to_string_lossy()
+                        .replace('\\', "/");
+                    files.push(FileInfo {
+                        name: file_name,
+                        path: entry.path().to_string_lossy().to_string(),
+                        size: metadata.len(),
+                        modified: modified_str,
+                        extension,
+                        compressed,
+                        relative_path,
+                        hash,
+                        modified_at: Some(modified),
+                    });
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+// File browser: Scan a gen_cpp directory for C++ files matching the
+// configured extensions (case-insensitive)
+fn scan_cpp_files(
+    gen_cpp_dir: &Path,
+    with_hash: bool,
+    extensions: &[String],
+    recursive: bool,
+    sort: SortKey,
+) -> FileListResult {
+    println!("[Rust] Looking in: {:?}", gen_cpp_dir);
+
+    // A regular file squatting on the directory path would make
+    // create_dir_all fail with a confusing error, so call it out explicitly
+    if gen_cpp_dir.exists() && !gen_cpp_dir.is_dir() {
+        println!("[Rust] ERROR: gen_cpp exists but is not a directory");
+        return FileListResult {
+            success: false,
+            files: vec![],
+            exists: None,
+            created: None,
+            skipped: None,
+            error: Some("gen_cpp exists but is not a directory".to_string()),
+        };
+    }
+
+    // Create unconditionally and lean on create_dir_all's idempotence:
+    // an exists() pre-check races against other windows scanning at the
+    // same time. AlreadyExists from a lost race is success; `existed`
+    // still lets the frontend tell "first run" from "emptied out".
+    let existed = gen_cpp_dir.is_dir();
+    if let Err(e) = fs::create_dir_all(gen_cpp_dir) {
+        if e.kind() != std::io::ErrorKind::AlreadyExists {
+            println!("[Rust] ERROR creating directory: {}", e);
+            return FileListResult {
+                success: false,
+                files: vec![],
+                exists: None,
+                created: None,
+                skipped: None,
+                error: Some(format!("Failed to create directory: {}", e)),
+            };
+        }
+    }
+
+    let mut files = Vec::new();
+
+    if let Err(e) = collect_cpp_files(
+        gen_cpp_dir,
+        Path::new(""),
+        0,
+        recursive,
+        with_hash,
+        extensions,
+        &mut files,
+    ) {
+        println!("[Rust] ERROR reading directory: {}", e);
+        return FileListResult {
+            success: false,
+            files: vec![],
+            exists: None,
+            created: None,
+            skipped: None,
+            error: Some(e),
+        };
+    }
+
+    files.sort_by(|a, b| match sort {
+        SortKey::Size => b
+            .size
+            .cmp(&a.size)
+            .then_with(|| natural_cmp(&a.relative_path, &b.relative_path)),
+        SortKey::Modified => b
+            .modified_at
+            .cmp(&a.modified_at)
+            .then_with(|| natural_cmp(&a.relative_path, &b.relative_path)),
+        _ => sort_key_cmp(sort, &a.relative_path, &b.relative_path),
+    });
+    println!("[Rust] Returning {} C++ files", files.len());
+
+    FileListResult {
+        success: true,
+        files,
+        exists: Some(existed),
+        created: Some(!existed),
+        skipped: None,
+        error: None,
+    }
+}
+
+// File browser: Get C++ files from ~/.madola/gen_cpp
+#[tauri::command]
+pub async fn get_cpp_files(
+    with_hash: Option<bool>,
+    recursive: Option<bool>,
+    sort: Option<SortKey>,
+    project: Option<String>,
+) -> FileListResult {
+    println!("[Rust] get_cpp_files called");
+    let with_hash = with_hash.unwrap_or(false);
+    let recursive = recursive.unwrap_or(false);
+    let sort = sort.unwrap_or_default();
+
+    let gen_cpp_dir = match madola_base() {
+        Ok(base) => base.join("gen_cpp"),
+        Err(e) => {
+            println!("[Rust] ERROR: {}", e);
+            return FileListResult {
+                success: false,
+                files: vec![],
+                exists: None,
+                created: None,
+                skipped: None,
+                error: Some(e),
+            };
+        }
+    };
+
+    let scan = move || {
+        let extensions = load_settings().cpp_extensions;
+        let mut result = scan_cpp_files(&gen_cpp_dir, with_hash, &extensions, recursive, sort);
+        if let Some(project) = project {
+            // Restrict the listing to the project's members
+            match load_project_map().get(&project) {
+                Some(members) => {
+                    result
+                        .files
+                        .retain(|f| members.contains(&f.relative_path));
+                }
+                None => {
+                    result.success = false;
+                    result.files = vec![];
+                    result.error = Some(format!("Unknown project: {}", project));
+                }
+            }
+        }
+        result
+    };
+    match with_timeout(scan).await {
+        Ok(result) => result,
+        Err(e) => {
+            println!("[Rust] ERROR scanning gen_cpp: {}", e);
+            FileListResult {
+                success: false,
+                files: vec![],
+                exists: None,
+                created: None,
+                skipped: None,
+                error: Some(e),
+            }
+        }
+    }
+}
+
+// Best-effort symbol outline: line-oriented heuristics tuned for the
+// regular shape of generated C++, NOT a real parse. Nested templates,
+// multi-line signatures, and exotic declarations may be missed.
+fn outline_from_source(source: &str) -> Vec<OutlineItem> {
+    const KEYWORDS: &[&str] = &[
+        "if", "else", "for", "while", "switch", "return", "catch", "sizeof", "do", "new", "delete",
+    ];
+
+    let mut items = Vec::new();
+    for (idx, raw) in source.lines().enumerate() {
+        let line = raw.trim();
+        let lineno = idx as u32 + 1;
+        if line.starts_with("//") || line.starts_with('#') || line.starts_with('*') {
+            continue;
+        }
+
+        // class/struct definitions; forward declarations (`class Foo;`)
+        // don't belong in an outline
+        let mut was_type = false;
+        for (prefix, kind) in [("class ", "class"), ("struct ", "struct")] {
+            if let Some(rest) = line.strip_prefix(prefix) {
+                was_type = true;
+                let name: String = rest
+                    .chars()
+                    .take_while(|c| c.is_alphanumeric() || *c == '_')
+                    .collect();
+                if !name.is_empty() && !rest[name.len()..].trim_start().starts_with(';') {
+                    items.push(OutlineItem {
+                        kind: kind.to_string(),
+                        name,
+                        line: lineno,
+                    });
+                }
+            }
+        }
+        if was_type {
+            continue;
+        }
+
+        // Function definition: identifier directly before '(' on a line
+        // that opens a body (or is continued), preceded by a return type
+        // or qualified with `::` as constructors are
+        if !line.ends_with('{') && !line.ends_with(')') {
+            continue;
+        }
+        let Some(paren) = line.find('(') else {
+            continue;
+        };
+        let before = &line[..paren];
+        let name_start = before
+            .rfind(|c: char| !(c.is_alphanumeric() || c == '_' || c == ':' || c == '~'))
+            .map(|i| i + 1)
+            .unwrap_or(0);
+        let qualified = &before[name_start..];
+        let name = qualified.rsplit("::").next().unwrap_or(qualified);
+        let has_return_type = before[..name_start]
+            .trim_end()
+            .ends_with(|c: char| c.is_alphanumeric() || c == '_' || c == '*' || c == '&' || c == '>');
+        if name.is_empty()
+            || name.starts_with(|c: char| c.is_ascii_digit())
+            || KEYWORDS.contains(&name)
+            || !(qualified.contains("::") || has_return_type)
+        {
+            continue;
+        }
+        items.push(OutlineItem {
+            kind: "function".to_string(),
+            name: name.to_string(),
+            line: lineno,
+        });
+    }
+    items
+}
+
+#[tauri::command]
+pub async fn cpp_outline(filename: String) -> Result<Vec<OutlineItem>, String> {
+    println!("[Rust] cpp_outline called: {}", filename);
+    validate_relative_cpp_path(&filename)?;
+
+    let read = move || {
+        // read_cpp_file enforces the size cap and rejects non-UTF-8 input
+        let result = read_cpp_file(filename);
+        match result.content {
+            Some(content) => Ok(outline_from_source(&content)),
+            None => Err(result
+                .error
+                .unwrap_or_else(|| "Failed to read file".to_string())),
+        }
+    };
+    with_timeout(read).await?
+}
+
+// Raw manifest shape of ~/.madola/projects.json: project name -> member
+// filenames (relative to gen_cpp). Missing or malformed manifests read as
+// empty rather than failing every project command.
+fn load_project_map() -> HashMap<String, Vec<String>> {
+    let Ok(path) = projects_path() else {
+        return HashMap::new();
+    };
+    match fs::read_to_string(&path) {
+        Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+            println!("[Rust] WARNING: ignoring malformed projects.json: {}", e);
+            HashMap::new()
+        }),
+        Err(_) => HashMap::new(),
+    }
+}
+
+fn save_project_map(map: &HashMap<String, Vec<String>>) -> Result<(), String> {
+    let path = projects_path()?;
+    let json = serde_json::to_string_pretty(map)
+        .map_err(|e| format!("Failed to serialize projects: {}", e))?;
+    fs::write(&path, json).map_err(|e| format!("Failed to write projects: {}", e))
+}
+
+// Split a project's members into live files and stale manifest entries
+// whose files no longer exist under gen_cpp
+fn project_from_entry(gen_cpp_dir: &Path, name: &str, members: &[String]) -> Project {
+    let mut files = Vec::new();
+    let mut stale = Vec::new();
+    for member in members {
+        if gen_cpp_dir.join(member).is_file() {
+            files.push(member.clone());
+        } else {
+            stale.push(member.clone());
+        }
+    }
+    Project {
+        name: name.to_string(),
+        files,
+        stale,
+    }
+}
+
+#[tauri::command]
+pub async fn get_projects() -> Result<Vec<Project>, String> {
+    println!("[Rust] get_projects called");
+    with_timeout(|| -> Result<Vec<Project>, String> {
+        let gen_cpp_dir = madola_base()?.join("gen_cpp");
+        let map = load_project_map();
+        let mut projects: Vec<Project> = map
+            .iter()
+            .map(|(name, members)| project_from_entry(&gen_cpp_dir, name, members))
+            .collect();
+        projects.sort_by(|a, b| natural_cmp(&a.name, &b.name));
+        Ok(projects)
+    })
+    .await?
+}
+
+#[tauri::command]
+pub async fn assign_file_to_project(filename: String, project: String) -> Result<(), String> {
+    println!(
+        "[Rust] assign_file_to_project called: {} -> {}",
+        filename, project
+    );
+    validate_relative_cpp_path(&filename)?;
+    // Project names follow the same rules as module names
+    validate_module_name(&project)?;
+
+    with_timeout(move || {
+        let gen_cpp_dir = madola_base()?.join("gen_cpp");
+        resolve_in_gen_cpp(&gen_cpp_dir, &filename)?;
+
+        let mut map = load_project_map();
+        // A file belongs to at most one project; reassigning moves it
+        for members in map.values_mut() {
+            members.retain(|m| *m != filename);
+        }
+        map.entry(project).or_default().push(filename);
+        map.retain(|_, members| !members.is_empty());
+        save_project_map(&map)
+    })
+    .await?
+}
+
+// One recursive pass over a directory, tallying file count and bytes per
+// lowercased extension. Extensionless files land in a "(no extension)"
+// bucket. Unreadable subdirectories are skipped, as in collect_cpp_files.
+fn collect_extension_stats(
+    dir: &Path,
+    depth: u32,
+    tally: &mut HashMap<String, (usize, u64)>,
+) -> Result<(), String> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if depth == 0 => return Err(format!("Failed to read directory: {}", e)),
+        Err(e) => {
+            println!("[Rust] Skipping unreadable directory {:?}: {}", dir, e);
+            return Ok(());
+        }
+    };
+    for entry in entries.flatten() {
+        if entry.file_type().map(|t| t.is_dir()).unwrap_or(false) {
+            if depth < MAX_SCAN_DEPTH {
+                collect_extension_stats(&entry.path(), depth + 1, tally)?;
+            }
+            continue;
+        }
+        let ext = entry
+            .file_name()
+            .to_str()
+            .and_then(file_extension)
+            .unwrap_or_else(|| "(no extension)".to_string());
+        let size = entry.metadata().map(|m| m.len()).unwrap_or(0);
+        let slot = tally.entry(ext).or_insert((0, 0));
+        slot.0 += 1;
+        slot.1 += size;
+    }
+    Ok(())
+}
+
+// Per-extension breakdown of gen_cpp, e.g. "42 .cpp, 12 .h", sorted by
+// descending count with the extension name as the tiebreak
+fn extension_stats(gen_cpp_dir: &Path) -> Result<Vec<ExtStat>, String> {
+    let mut tally = HashMap::new();
+    if gen_cpp_dir.is_dir() {
+        collect_extension_stats(gen_cpp_dir, 0, &mut tally)?;
+    }
+    let mut stats: Vec<ExtStat> = tally
+        .into_iter()
+        .map(|(ext, (count, total_bytes))| ExtStat {
+            ext,
+            count,
+            total_bytes,
+        })
+        .collect();
+    stats.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| natural_cmp(&a.ext, &b.ext)));
+    Ok(stats)
+}
+
+// Dashboard: count files and bytes per extension across gen_cpp
+#[tauri::command]
+pub async fn gen_cpp_extension_stats() -> Result<Vec<ExtStat>, String> {
+    println!("[Rust] gen_cpp_extension_stats called");
+    let gen_cpp_dir = madola_base()?.join("gen_cpp");
+    with_timeout(move || extension_stats(&gen_cpp_dir)).await?
+}
+
+// Built-in templates for create_cpp_file: (id, display name, description)
+const CPP_TEMPLATES: &[(&str, &str, &str)] = &[
+    ("main", "Main program", "A minimal program with a main() entry point"),
+    ("class", "Class skeleton", "A guarded class definition with constructor stubs"),
+    ("module", "MADOLA module", "A function skeleton for generated MADOLA math code"),
+];
+
+// Companion metadata file for a user template: <stem>.json next to <stem>.cpp
+#[derive(serde::Deserialize)]
+struct TemplateMeta {
+    name: String,
+    #[serde(default)]
+    description: String,
+}
+
+// Metadata from a template's leading comment block: the first `//` line is
+// the display name, any further leading comment lines join into the
+// description
+fn template_header_meta(path: &Path) -> Option<(String, String)> {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) => {
+            println!("[Rust] WARNING: skipping template {:?}: {}", path, e);
+            return None;
+        }
+    };
+    let comments: Vec<&str> = content
+        .lines()
+        .map_while(|line| line.strip_prefix("//").map(str::trim))
+        .filter(|line| !line.is_empty())
+        .collect();
+    match comments.split_first() {
+        Some((name, rest)) => Some((name.to_string(), rest.join(" "))),
+        None => {
+            println!(
+                "[Rust] WARNING: skipping template {:?}: no metadata json or leading comment",
+                path
+            );
+            None
+        }
+    }
+}
+
+// User templates from ~/.madola/templates/*.cpp. Malformed entries are
+// skipped with a logged warning so one bad file doesn't hide the rest.
+fn collect_user_templates(templates_dir: &Path) -> Vec<TemplateInfo> {
+    let mut templates = Vec::new();
+    let entries = match fs::read_dir(templates_dir) {
+        Ok(entries) => entries,
+        // No user template directory is the common case, not an error
+        Err(_) => return templates,
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.extension().map(|e| e != "cpp").unwrap_or(true) {
+            continue;
+        }
+        let id = match path.file_stem().and_then(|s| s.to_str()) {
+            Some(stem) => stem.to_string(),
+            None => continue,
+        };
+        let meta_path = path.with_extension("json");
+        let meta = if meta_path.exists() {
+            let parsed = fs::read_to_string(&meta_path)
+                .ok()
+                .and_then(|content| serde_json::from_str::<TemplateMeta>(&content).ok());
+            match parsed {
+                Some(meta) => Some((meta.name, meta.description)),
+                None => {
+                    println!(
+                        "[Rust] WARNING: skipping template '{}': invalid metadata json",
+                        id
+                    );
+                    None
+                }
+            }
+        } else {
+            template_header_meta(&path)
+        };
+        if let Some((name, description)) = meta {
+            templates.push(TemplateInfo {
+                id,
+                name,
+                description,
+            });
+        }
+    }
+    templates.sort_by(|a, b| natural_cmp(&a.id, &b.id));
+    templates
+}
+
+// Template picker: the built-in templates plus any user templates found in
+// ~/.madola/templates
+#[tauri::command]
+pub async fn list_cpp_templates() -> Result<Vec<TemplateInfo>, String> {
+    println!("[Rust] list_cpp_templates called");
+    let templates_dir = madola_base()?.join("templates");
+    with_timeout(move || {
+        let mut templates: Vec<TemplateInfo> = CPP_TEMPLATES
+            .iter()
+            .map(|(id, name, description)| TemplateInfo {
+                id: id.to_string(),
+                name: name.to_string(),
+                description: description.to_string(),
+            })
+            .collect();
+        templates.extend(collect_user_templates(&templates_dir));
+        templates
+    })
+    .await
+}
+
+// Render a built-in template for the given file, or None for an unknown id
+fn cpp_template_content(template_id: &str, filename: &str) -> Option<String> {
+    let stem = Path::new(filename)
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("generated");
+    let guard: String = stem
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_uppercase() } else { '_' })
+        .collect();
+
+    match template_id {
+        "main" => Some(format!(
+            "// {}\n#include <iostream>\n\nint main() {{\n    std::cout << \"Hello from {}\" << std::endl;\n    return 0;\n}}\n",
+            filename, stem
+        )),
+        "class" => Some(format!(
+            "// {}\n#ifndef {guard}_CPP\n#define {guard}_CPP\n\nclass {stem} {{\npublic:\n    {stem}() = default;\n    ~{stem}() = default;\n}};\n\n#endif // {guard}_CPP\n",
+            filename,
+            guard = guard,
+            stem = stem
+        )),
+        "module" => Some(format!(
+            "// {}\n// Generated MADOLA module skeleton\n#include <cmath>\n\ndouble {}_eval(double x) {{\n    return x;\n}}\n",
+            filename, stem
+        )),
+        _ => None,
+    }
+}
+
+// File browser: Create a new C++ file, optionally from a built-in template
+#[tauri::command]
+pub async fn create_cpp_file(filename: String, template: Option<String>) -> FileContentResult {
+    println!("[Rust] create_cpp_file called: {} ({:?})", filename, template);
+
+    let fail = |error: String| FileContentResult {
+        success: false,
+        content: None,
+        filename: None,
+        has_bom: None,
+        line_ending: None,
+        line_count: None,
+        char_count: None,
+        error: Some(error),
+    };
+
+    if let Err(e) = validate_cpp_filename(&filename) {
+        return fail(e);
+    }
+
+    let create = move || -> FileContentResult {
+        let gen_cpp_dir = match madola_base() {
+            Ok(base) => base.join("gen_cpp"),
+            Err(e) => return fail(e),
+        };
+        if let Err(e) = fs::create_dir_all(&gen_cpp_dir) {
+            return fail(format!("Failed to create directory: {}", e));
+        }
+
+        let file_path = gen_cpp_dir.join(&filename);
+        if file_path.exists() {
+            return fail(format!("A file named {} already exists", filename));
+        }
+
+        let content = match template.as_deref() {
+            None => String::new(),
+            Some(id) => match cpp_template_content(id, &filename) {
+                Some(content) => content,
+                None => return fail(format!("Unknown template: {}", id)),
+            },
+        };
+
+        if let Err(e) = fs::write(&file_path, &content) {
+            return fail(format!("Failed to write file: {}", e));
+        }
+
+        let (line_count, char_count) = count_lines_chars(&content);
+        FileContentResult {
+            success: true,
+            content: Some(content),
+            filename: Some(filename),
+            has_bom: Some(false),
+            line_ending: Some("lf".to_string()),
+            line_count: Some(line_count),
+            char_count: Some(char_count),
+            error: None,
+        }
+    };
+    with_timeout(create).await.unwrap_or_else(fail)
+}
+
+// Largest input either diff side may have; beyond this the UI should fall
+// back to a plain "file changed" notice
+const MAX_DIFF_BYTES: usize = 10 * 1024 * 1024;
+
+// Diff the on-disk file against supplied content, for review-before-save
+#[tauri::command]
+pub async fn diff_cpp_content(filename: String, other: String) -> Result<Vec<DiffLine>, String> {
+    println!("[Rust] diff_cpp_content called: {}", filename);
+    validate_relative_cpp_path(&filename)?;
+
+    // Reading and diffing up to 10 MB a side is blocking work
+    with_timeout(move || -> Result<Vec<DiffLine>, String> {
+        let file_path = madola_base()?.join("gen_cpp").join(&filename);
+        let metadata = fs::metadata(&file_path)
+            .map_err(|e| format!("Failed to stat file: {}", e))?;
+        if metadata.len() as usize > MAX_DIFF_BYTES || other.len() > MAX_DIFF_BYTES {
+            return Err(format!(
+                "Content too large to diff (limit {} bytes)",
+                MAX_DIFF_BYTES
+            ));
+        }
+
+        let on_disk = match fs::read(&file_path) {
+            Ok(bytes) => String::from_utf8(bytes)
+                .map_err(|_| "File is not valid UTF-8".to_string())?,
+            Err(e) => return Err(format!("Failed to read file: {}", e)),
+        };
+
+        let diff = similar::TextDiff::from_lines(&on_disk, &other);
+        let lines = diff
+            .iter_all_changes()
+            .map(|change| DiffLine {
+                tag: match change.tag() {
+                    similar::ChangeTag::Insert => "add",
+                    similar::ChangeTag::Delete => "del",
+                    similar::ChangeTag::Equal => "eq",
+                }
+                .to_string(),
+                text: change.value().trim_end_matches('\n').to_string(),
+            })
+            .collect();
+        Ok(lines)
+    })
+    .await?
+}
+
+// Decompress a .gz file into a String, refusing to inflate past the limit
+// so a decompression bomb cannot exhaust memory
+fn read_gz_to_string(path: &Path, limit: u64) -> Result<String, String> {
+    use std::io::Read;
+
+    let file = fs::File::open(path).map_err(|e| format!("Failed to open file: {}", e))?;
+    let mut decoder = flate2::read::GzDecoder::new(file).take(limit + 1);
+    let mut content = String::new();
+    decoder
+        .read_to_string(&mut content)
+        .map_err(|e| format!("Failed to decompress file: {}", e))?;
+    if content.len() as u64 > limit {
+        return Err(format!(
+            "file too large after decompression (limit {})",
+            limit
+        ));
+    }
+    Ok(content)
+}
+
+// Synchronous body of get_cpp_file_content, shared with the batch command
+fn read_cpp_file(filename: String) -> FileContentResult {
+    let resolved = madola_base()
+        .map(|base| base.join("gen_cpp"))
+        .and_then(|dir| resolve_in_gen_cpp(&dir, &filename));
+    let file_path = match resolved {
+        Ok(path) => path,
+        Err(e) => {
+            return FileContentResult {
+                success: false,
+                content: None,
+                filename: None,
+                has_bom: None,
+                line_ending: None,
+                line_count: None,
+                char_count: None,
+                error: Some(e),
+            };
+        }
+    };
+
+    read_resolved_cpp(&file_path, filename)
+}
+
+// Shared reading tail for the managed and external browsers: size cap,
+// transparent .gz decompression, BOM stripping, and counts
+fn read_resolved_cpp(file_path: &Path, filename: String) -> FileContentResult {
+    let limit = load_settings().max_file_size_bytes;
+    if let Err(e) = check_file_size(file_path, limit) {
+        return FileContentResult {
+            success: false,
+            error: Some(e),
+            ..Default::default()
+        };
+    }
+
+    // .gz files are decompressed transparently; plain files read as-is
+    let raw = if filename.to_lowercase().ends_with(".gz") {
+        read_gz_to_string(file_path, limit)
+    } else {
+        fs::read_to_string(file_path).map_err(|e| format!("Failed to read file: {}", e))
+    };
+
+    match raw {
+        Ok(content) => {
+            let (content, has_bom) = strip_bom(content);
+            let line_ending = detect_line_ending(&content).to_string();
+            let (line_count, char_count) = count_lines_chars(&content);
+            FileContentResult {
+                success: true,
+                content: Some(content),
+                filename: Some(filename),
+                has_bom: Some(has_bom),
+                line_ending: Some(line_ending),
+                line_count: Some(line_count),
+                char_count: Some(char_count),
+                error: None,
+            }
+        }
+        Err(e) => FileContentResult {
+            success: false,
+            content: None,
+            filename: None,
+            has_bom: None,
+            line_ending: None,
+            line_count: None,
+            char_count: None,
+            error: Some(e),
+        },
+    }
+}
+
+// Cap on remembered external directories in settings
+const RECENT_EXTERNAL_DIRS_LIMIT: usize = 10;
+
+// Most-recent-first, deduped; best-effort since failing to remember a
+// directory must not fail the listing itself
+fn remember_external_dir(dir: &Path) {
+    let mut settings = load_settings();
+    let entry = dir.to_string_lossy().to_string();
+    settings.recent_external_dirs.retain(|d| *d != entry);
+    settings.recent_external_dirs.insert(0, entry);
+    settings.recent_external_dirs.truncate(RECENT_EXTERNAL_DIRS_LIMIT);
+    if let Err(e) = save_settings(&settings) {
+        println!("[Rust] WARNING: could not remember external dir: {}", e);
+    }
+}
+
+// Browse a directory outside the managed workspace (e.g. a git checkout)
+// with the same scan used for gen_cpp. Unlike gen_cpp, a missing directory
+// is an error here, not something to create.
+#[tauri::command]
+pub async fn list_external_cpp(
+    dir: String,
+    recursive: Option<bool>,
+    sort: Option<SortKey>,
+) -> FileListResult {
+    println!("[Rust] list_external_cpp called: {}", dir);
+
+    let fail = |error: String| FileListResult {
+        success: false,
+        files: vec![],
+        exists: None,
+        created: None,
+        skipped: None,
+        error: Some(error),
+    };
+
+    let resolved = match resolve_existing_path(&dir) {
+        Ok(path) => path,
+        Err(e) => return fail(e),
+    };
+    if !resolved.is_dir() {
+        return fail(format!("Not a directory: {}", dir));
+    }
+    remember_external_dir(&resolved);
+
+    let scan = move || {
+        let extensions = load_settings().cpp_extensions;
+        scan_cpp_files(
+            &resolved,
+            false,
+            &extensions,
+            recursive.unwrap_or(false),
+            sort.unwrap_or_default(),
+        )
+    };
+    match with_timeout(scan).await {
+        Ok(result) => result,
+        Err(e) => fail(e),
+    }
+}
+
+// Read a file from an externally browsed directory; `filename` is relative
+// to `dir` and confined to it exactly like gen_cpp reads are
+#[tauri::command]
+pub async fn read_external_file(dir: String, filename: String) -> FileContentResult {
+    println!("[Rust] read_external_file called: {}/{}", dir, filename);
+
+    let read = move || {
+        let root = match resolve_existing_path(&dir) {
+            Ok(path) => path,
+            Err(e) => {
+                return FileContentResult {
+                    success: false,
+                    error: Some(e),
+                    ..Default::default()
+                }
+            }
+        };
+        match resolve_external(&root, &filename) {
+            Ok(path) => read_resolved_cpp(&path, filename),
+            Err(e) => FileContentResult {
+                success: false,
+                error: Some(e),
+                ..Default::default()
+            },
+        }
+    };
+    match with_timeout(read).await {
+        Ok(result) => result,
+        Err(e) => FileContentResult {
+            success: false,
+            error: Some(e),
+            ..Default::default()
+        },
+    }
+}
+
+// File browser: Get C++ file content (accepts a gen_cpp-relative path)
+#[tauri::command]
+pub async fn get_cpp_file_content(filename: String) -> FileContentResult {
+    match with_timeout(move || read_cpp_file(filename)).await {
+        Ok(result) => result,
+        Err(e) => FileContentResult {
+            success: false,
+            error: Some(e),
+            ..Default::default()
+        },
+    }
+}
+
+// Number of files the batch read command touches at once
+const BATCH_READ_CONCURRENCY: usize = 4;
+
+// File browser: Read several C++ files in one IPC round trip. Each entry
+// succeeds or fails independently so one bad name doesn't sink the batch.
+#[tauri::command]
+pub async fn get_cpp_files_content(filenames: Vec<String>) -> Vec<FileContentResult> {
+    println!("[Rust] get_cpp_files_content called for {} files", filenames.len());
+
+    let semaphore = Arc::new(tokio::sync::Semaphore::new(BATCH_READ_CONCURRENCY));
+    let mut tasks = Vec::new();
+    for filename in filenames {
+        let semaphore = semaphore.clone();
+        tasks.push(tauri::async_runtime::spawn(async move {
+            let _permit = semaphore.acquire_owned().await;
+            tauri::async_runtime::spawn_blocking(move || read_cpp_file(filename))
+                .await
+                .unwrap_or_else(|e| FileContentResult {
+                    success: false,
+                    error: Some(format!("Blocking task failed: {}", e)),
+                    ..Default::default()
+                })
+        }));
+    }
+
+    let mut results = Vec::new();
+    for task in tasks {
+        match task.await {
+            Ok(result) => results.push(result),
+            Err(e) => results.push(FileContentResult {
+                success: false,
+                error: Some(format!("Task failed: {}", e)),
+                ..Default::default()
+            }),
+        }
+    }
+    results
+}
+
+// Trash any existing content and write the new content while holding the
+// file's advisory lock. Both steps sit under one hold of the lock so a
+// concurrent save or delete of the same name cannot slip in between the
+// existence check and the rename -- two racing saves would otherwise both
+// see the file present and one rename would fail with NotFound.
+fn save_cpp_file_locked(
+    locks: &FileLocks,
+    history: &TrashHistory,
+    gen_cpp_dir: &Path,
+    trash_dir: &Path,
+    filename: &str,
+    content: &str,
+) -> Result<(), String> {
+    let lock = locks.lock_for(filename);
+    let _guard = lock.lock().unwrap();
+    // An overwrite preserves the old content in the trash for undo
+    trash_existing_file(history, trash_dir, filename, &gen_cpp_dir.join(filename))?;
+    if filename.to_lowercase().ends_with(".gz") {
+        // A .gz name is re-compressed on the way back to disk
+        use std::io::Write;
+        let file = fs::File::create(gen_cpp_dir.join(filename))
+            .map_err(|e| format!("Failed to create file: {}", e))?;
+        let mut encoder = flate2::write::GzEncoder::new(file, flate2::Compression::default());
+        encoder
+            .write_all(content.as_bytes())
+            .map_err(|e| format!("Failed to write file: {}", e))?;
+        encoder
+            .finish()
+            .map(|_| ())
+            .map_err(|e| format!("Failed to write file: {}", e))
+    } else {
+        fs::write(gen_cpp_dir.join(filename), content)
+            .map_err(|e| format!("Failed to write file: {}", e))
+    }
+}
+
+// File browser: Save a C++ file into ~/.madola/gen_cpp
+#[tauri::command]
+pub async fn save_cpp_file(
+    filename: String,
+    content: String,
+    line_ending: Option<String>,
+    add_bom: Option<bool>,
+    locks: tauri::State<'_, FileLocks>,
+    history: tauri::State<'_, TrashHistory>,
+) -> Result<(), String> {
+    println!("[Rust] save_cpp_file called: {}", filename);
+    validate_cpp_filename(&filename)?;
+
+    // The write (and any wait on the file's lock) happens on the blocking
+    // pool, never on the main thread
+    let locks = locks.inner().clone();
+    let history = history.inner().clone();
+    with_timeout(move || {
+        let base = madola_base()?;
+        let gen_cpp_dir = base.join("gen_cpp");
+        fs::create_dir_all(&gen_cpp_dir)
+            .map_err(|e| format!("Failed to create directory: {}", e))?;
+
+        let content = apply_save_style(content, line_ending.as_deref(), add_bom.unwrap_or(false));
+        save_cpp_file_locked(
+            &locks,
+            &history,
+            &gen_cpp_dir,
+            &base.join(".trash"),
+            &filename,
+            &content,
+        )
+    })
+    .await?
+}
+
+// File browser: Delete a C++ file from ~/.madola/gen_cpp
+#[tauri::command]
+pub async fn delete_cpp_file(
+    filename: String,
+    locks: tauri::State<'_, FileLocks>,
+    history: tauri::State<'_, TrashHistory>,
+) -> Result<(), String> {
+    println!("[Rust] delete_cpp_file called: {}", filename);
+    validate_cpp_filename(&filename)?;
+
+    let locks = locks.inner().clone();
+    let history = history.inner().clone();
+    with_timeout(move || {
+        let base = madola_base()?;
+        let file_path = base.join("gen_cpp").join(&filename);
+        if !file_path.exists() {
+            return Err("File not found".to_string());
+        }
+        let lock = locks.lock_for(&filename);
+        let _guard = lock.lock().unwrap();
+        // Deleting moves the content into the trash so it can be undone
+        trash_existing_file(&history, &base.join(".trash"), &filename, &file_path)
+    })
+    .await?
+}
+
+// A collision-free name in `dir`: foo.cpp, then foo-1.cpp, foo-2.cpp, ...
+fn collision_free_target(dir: &Path, name: &str) -> PathBuf {
+    let original = dir.join(name);
+    if !original.exists() {
+        return original;
+    }
+    let (stem, ext) = match name.split_once('.') {
+        Some((stem, ext)) => (stem, format!(".{}", ext)),
+        None => (name, String::new()),
+    };
+    let mut n = 1;
+    loop {
+        let candidate = dir.join(format!("{}-{}{}", stem, n, ext));
+        if !candidate.exists() {
+            return candidate;
+        }
+        n += 1;
+    }
+}
+
+// Copy dropped files into the workspace: .cpp into gen_cpp, .wasm/.js into
+// the configured trove module. Each file succeeds or fails independently so
+// one bad path doesn't abort the batch.
+pub fn import_dropped_files(base: &Path, module: &str, paths: &[PathBuf]) -> Vec<ImportedFile> {
+    let mut results = Vec::new();
+    for path in paths {
+        let source = path.to_string_lossy().to_string();
+        let fail = |error: String| ImportedFile {
+            source: source.clone(),
+            target: String::new(),
+            success: false,
+            error: Some(error),
+        };
+
+        let name = match path.file_name().and_then(|n| n.to_str()) {
+            Some(name) => name.to_string(),
+            None => {
+                results.push(fail("Failed to get file name".to_string()));
+                continue;
+            }
+        };
+        let dest_dir = match file_extension(&name).as_deref() {
+            Some("cpp") => base.join("gen_cpp"),
+            Some("wasm") | Some("js") => base.join("trove").join(module),
+            _ => {
+                results.push(fail("Unsupported file type".to_string()));
+                continue;
+            }
+        };
+
+        let outcome = (|| -> Result<PathBuf, String> {
+            fs::create_dir_all(&dest_dir)
+                .map_err(|e| format!("Failed to create directory: {}", e))?;
+            let target = collision_free_target(&dest_dir, &name);
+            fs::copy(path, &target).map_err(|e| format!("Failed to copy file: {}", e))?;
+            Ok(target)
+        })();
+        results.push(match outcome {
+            Ok(target) => {
+                println!("[Rust] Imported dropped file: {:?}", target);
+                ImportedFile {
+                    source,
+                    target: target.to_string_lossy().to_string(),
+                    success: true,
+                    error: None,
+                }
+            }
+            Err(e) => fail(e),
+        });
+    }
+    results
+}
+
+// Move a file, falling back to copy+delete when rename fails (e.g. across
+// filesystems). The fast rename path is atomic on the same filesystem; the
+// fallback hashes the source before copying and re-verifies the copy before
+// the source is deleted, so a flaky mount can never lose the only copy.
+fn move_file(source: &Path, target: &Path) -> Result<(), String> {
+    if fs::rename(source, target).is_ok() {
+        return Ok(());
+    }
+    let expected = hash_file_streaming(source)?;
+    fs::copy(source, target).map_err(|e| format!("Failed to copy file: {}", e))?;
+    let actual = hash_file_streaming(target)?;
+    if actual != expected {
+        // The source stays; the bad copy is best-effort cleaned up
+        let _ = fs::remove_file(target);
+        return Err("copy verification failed: destination hash mismatch".to_string());
+    }
+    fs::remove_file(source).map_err(|e| format!("Failed to remove original: {}", e))
+}
+
+// Promote a generated file into a trove module, e.g. to archive the source
+// next to its compiled artifacts
+#[tauri::command]
+pub async fn move_cpp_to_module(filename: String, module_name: String) -> Result<(), String> {
+    println!(
+        "[Rust] move_cpp_to_module called: {} -> {}",
+        filename, module_name
+    );
+    validate_cpp_filename(&filename)?;
+    validate_module_name(&module_name)?;
+
+    let base = madola_base()?;
+    let source = base.join("gen_cpp").join(&filename);
+    if !source.is_file() {
+        return Err(format!("File not found: {}", filename));
+    }
+    let module_dir = base.join("trove").join(&module_name);
+    fs::create_dir_all(&module_dir)
+        .map_err(|e| format!("Failed to create directory: {}", e))?;
+    let target = module_dir.join(&filename);
+    if target.exists() {
+        return Err(format!(
+            "{} already exists in module {}",
+            filename, module_name
+        ));
+    }
+
+    with_timeout(move || move_file(&source, &target)).await?
+}
+
+// Restore the most recently trashed file back into gen_cpp and return the
+// refreshed listing
+#[tauri::command]
+pub async fn undo_last_operation(
+    history: tauri::State<'_, TrashHistory>,
+) -> Result<FileListResult, String> {
+    println!("[Rust] undo_last_operation called");
+    let history = history.inner().clone();
+    with_timeout(move || undo_last_operation_in(&history)).await
+}
+
+// Synchronous body of undo_last_operation: the restore rename plus the full
+// rescan, run off the main thread
+fn undo_last_operation_in(history: &TrashHistory) -> FileListResult {
+    let fail = |error: String| FileListResult {
+        success: false,
+        files: vec![],
+        exists: None,
+        created: None,
+        skipped: None,
+        error: Some(error),
+    };
+
+    let entry = match history.0.lock().unwrap().pop() {
+        Some(entry) => entry,
+        None => return fail("nothing to undo".to_string()),
+    };
+
+    let base = match madola_base() {
+        Ok(base) => base,
+        Err(e) => return fail(e),
+    };
+    let gen_cpp_dir = base.join("gen_cpp");
+    if let Err(e) = fs::create_dir_all(&gen_cpp_dir) {
+        return fail(format!("Failed to create directory: {}", e));
+    }
+
+    let target = restore_target(&gen_cpp_dir, &entry.filename);
+    if let Err(e) = fs::rename(&entry.trash_path, &target) {
+        // Put the entry back so a transient failure can be retried
+        history.0.lock().unwrap().push(entry);
+        return fail(format!("Failed to restore file: {}", e));
+    }
+    println!("[Rust] Restored {:?}", target);
+
+    let extensions = load_settings().cpp_extensions;
+    scan_cpp_files(&gen_cpp_dir, false, &extensions, false, SortKey::default())
+}
+
+// File browser: Rename a C++ file within ~/.madola/gen_cpp
+#[tauri::command]
+pub async fn rename_cpp_file(
+    old_name: String,
+    new_name: String,
+    locks: tauri::State<'_, FileLocks>,
+) -> Result<(), String> {
+    println!("[Rust] rename_cpp_file called: {} -> {}", old_name, new_name);
+    validate_cpp_filename(&old_name)?;
+    validate_cpp_filename(&new_name)?;
+    if old_name == new_name {
+        return Err("New name is the same as the old name".to_string());
+    }
+
+    let locks = locks.inner().clone();
+    with_timeout(move || {
+        let gen_cpp_dir = madola_base()?.join("gen_cpp");
+        let new_path = gen_cpp_dir.join(&new_name);
+        if new_path.exists() {
+            return Err(format!("A file named {} already exists", new_name));
+        }
+
+        // Lock both names in a stable order so two opposing renames can't
+        // deadlock
+        let (first, second) = if old_name <= new_name {
+            (old_name.clone(), new_name.clone())
+        } else {
+            (new_name.clone(), old_name.clone())
+        };
+        let first_lock = locks.lock_for(&first);
+        let _first_guard = first_lock.lock().unwrap();
+        let second_lock = locks.lock_for(&second);
+        let _second_guard = second_lock.lock().unwrap();
+
+        // Same directory, so the rename is normally atomic; move_file covers
+        // the odd mount where it isn't
+        move_file(&gen_cpp_dir.join(&old_name), &new_path)
+    })
+    .await?
+}
+
+// Smallest gap between progress emissions (~20 updates/second), so huge
+// batches don't flood the event channel
+const PROGRESS_EMIT_INTERVAL_MS: u64 = 50;
+
+// Throttled per-file progress reporting for the zip export/import commands
+struct ProgressEmitter {
+    window: tauri::Window,
+    // Wraps each payload in the right AppEvent variant (export vs import)
+    event: fn(ProgressPayload) -> AppEvent,
+    total: usize,
+    last_emit: Option<std::time::Instant>,
+}
+
+impl ProgressEmitter {
+    fn new(window: tauri::Window, event: fn(ProgressPayload) -> AppEvent, total: usize) -> Self {
+        ProgressEmitter {
+            window,
+            event,
+            total,
+            last_emit: None,
+        }
+    }
+
+    fn report(&mut self, done: usize, current: &str) {
+        let due = self
+            .last_emit
+            .map(|at| at.elapsed().as_millis() as u64 >= PROGRESS_EMIT_INTERVAL_MS)
+            .unwrap_or(true);
+        if due || done == self.total {
+            let _ = emit_event(
+                &self.window,
+                (self.event)(ProgressPayload {
+                    done,
+                    total: self.total,
+                    current: current.to_string(),
+                }),
+            );
+            self.last_emit = Some(std::time::Instant::now());
+        }
+    }
+}
+
+// Zip export: Pack all C++ files from ~/.madola/gen_cpp into a zip archive
+#[tauri::command]
+pub async fn export_gen_cpp_zip(
+    window: tauri::Window,
+    dest: String,
+    op_id: Option<String>,
+) -> Result<usize, String> {
+    println!("[Rust] export_gen_cpp_zip called, dest: {}", dest);
+
+    let cancel = window.state::<CancelFlags>().register(&op_id);
+
+    // The whole pack loop is file I/O plus compression; it runs on the
+    // blocking pool, never on the async executor. Deliberately no command
+    // timeout: large exports legitimately run long, and the progress
+    // events plus the cooperative cancel flag already cover them.
+    let win = window.clone();
+    let result = tauri::async_runtime::spawn_blocking(move || -> Result<usize, String> {
+        let gen_cpp_dir = madola_base()?.join("gen_cpp");
+        if !gen_cpp_dir.is_dir() {
+            return Err("gen_cpp directory does not exist".to_string());
+        }
+
+        let entries = fs::read_dir(&gen_cpp_dir)
+            .map_err(|e| format!("Failed to read directory: {}", e))?;
+        // The export covers everything the scan lists, .cpp.gz included
+        let extensions = load_settings().cpp_extensions;
+        let mut names: Vec<String> = entries
+            .flatten()
+            .filter_map(|entry| entry.file_name().into_string().ok())
+            .filter(|name| managed_cpp_name(name, &extensions))
+            .collect();
+        names.sort();
+
+        let file = fs::File::create(&dest)
+            .map_err(|e| format!("Failed to create zip file: {}", e))?;
+        let mut zip = zip::ZipWriter::new(file);
+        let options = zip::write::FileOptions::default()
+            .compression_method(zip::CompressionMethod::Deflated);
+
+        let mut emitter = ProgressEmitter::new(win.clone(), AppEvent::ExportProgress, names.len());
+        let mut count = 0;
+        for file_name in &names {
+            // Checked between files: a cancelled export drops the partial zip
+            if cancel.load(Ordering::Relaxed) {
+                drop(zip);
+                let _ = fs::remove_file(&dest);
+                println!("[Rust] Export cancelled after {} files", count);
+                return Err(CANCELLED_MSG.to_string());
+            }
+            let content = fs::read(gen_cpp_dir.join(file_name))
+                .map_err(|e| format!("Failed to read {}: {}", file_name, e))?;
+            zip.start_file(file_name, options)
+                .map_err(|e| format!("Failed to add {} to zip: {}", file_name, e))?;
+            use std::io::Write;
+            zip.write_all(&content)
+                .map_err(|e| format!("Failed to write {} to zip: {}", file_name, e))?;
+            count += 1;
+            emitter.report(count, file_name);
+        }
+
+        zip.finish()
+            .map_err(|e| format!("Failed to finish zip: {}", e))?;
+        let _ = emit_event(&win, AppEvent::ExportDone(count));
+        println!("[Rust] Exported {} C++ files to {}", count, dest);
+        Ok(count)
+    })
+    .await
+    .map_err(|e| format!("Blocking task failed: {}", e))
+    .and_then(|result| result);
+
+    window.state::<CancelFlags>().finish(&op_id);
+    result
+}
+
+// Zip import: Extract .cpp entries from a zip archive into ~/.madola/gen_cpp
+#[tauri::command]
+pub async fn import_gen_cpp_zip(
+    window: tauri::Window,
+    src: String,
+    overwrite: bool,
+    op_id: Option<String>,
+) -> FileListResult {
+    println!("[Rust] import_gen_cpp_zip called, src: {}, overwrite: {}", src, overwrite);
+
+    let gen_cpp_dir = match madola_base() {
+        Ok(base) => base.join("gen_cpp"),
+        Err(e) => {
+            return FileListResult {
+                success: false,
+                files: vec![],
+                exists: None,
+                created: None,
+                skipped: None,
+                error: Some(e),
+            };
+        }
+    };
+
+    if let Err(e) = fs::create_dir_all(&gen_cpp_dir) {
+        return FileListResult {
+            success: false,
+            files: vec![],
+            exists: None,
+            created: None,
+            skipped: None,
+            error: Some(format!("Failed to create directory: {}", e)),
+        };
+    }
+
+    // Registered only once the early validations are done, so every exit
+    // from here on goes through finish
+    let cancel = window.state::<CancelFlags>().register(&op_id);
+
+    // The unpack loop is blocking work like the export side, and runs
+    // without the command timeout for the same reason; only the final
+    // listing refresh stays on the async executor
+    let win = window.clone();
+    let result = tauri::async_runtime::spawn_blocking(move || -> Result<usize, String> {
+        let file = fs::File::open(&src)
+            .map_err(|e| format!("Failed to open zip file: {}", e))?;
+        let mut archive = zip::ZipArchive::new(file)
+            .map_err(|e| format!("Failed to read zip file: {}", e))?;
+
+        let total = archive
+            .file_names()
+            .filter(|name| name.ends_with(".cpp"))
+            .count();
+        let mut emitter = ProgressEmitter::new(win.clone(), AppEvent::ImportProgress, total);
+        let mut processed = 0;
+        let mut skipped = 0;
+        for i in 0..archive.len() {
+            // Checked between entries; files already extracted stay in place
+            if cancel.load(Ordering::Relaxed) {
+                println!("[Rust] Import cancelled after {} entries", processed);
+                return Err(CANCELLED_MSG.to_string());
+            }
+            let mut entry = archive.by_index(i)
+                .map_err(|e| format!("Failed to read zip entry: {}", e))?;
+
+            // Ignore non-.cpp entries silently
+            if !entry.name().ends_with(".cpp") {
+                continue;
+            }
+
+            // Guard against zip-slip: the normalized entry path must stay
+            // inside gen_cpp
+            let relative = entry.enclosed_name()
+                .ok_or_else(|| format!("Rejecting unsafe zip entry: {}", entry.name()))?
+                .to_path_buf();
+            let target = gen_cpp_dir.join(&relative);
+            if !target.starts_with(&gen_cpp_dir) {
+                return Err(format!("Rejecting unsafe zip entry: {}", entry.name()));
+            }
+
+            if target.exists() && !overwrite {
+                println!("[Rust] Skipping existing file: {:?}", relative);
+                skipped += 1;
+                processed += 1;
+                emitter.report(processed, entry.name());
+                continue;
+            }
+
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent)
+                    .map_err(|e| format!("Failed to create directory: {}", e))?;
+            }
+            let mut content = Vec::new();
+            use std::io::Read;
+            entry.read_to_end(&mut content)
+                .map_err(|e| format!("Failed to read zip entry: {}", e))?;
+            fs::write(&target, content)
+                .map_err(|e| format!("Failed to write {:?}: {}", relative, e))?;
+            println!("[Rust] Imported: {:?}", relative);
+            processed += 1;
+            emitter.report(processed, entry.name());
+        }
+        let _ = emit_event(&win, AppEvent::ImportDone(processed));
+        Ok(skipped)
+    })
+    .await
+    .map_err(|e| format!("Blocking task failed: {}", e))
+    .and_then(|result| result);
+
+    window.state::<CancelFlags>().finish(&op_id);
+    match result {
+        Ok(skipped) => {
+            let mut list = get_cpp_files(None, None, None, None).await;
+            list.skipped = Some(skipped);
+            list
+        }
+        Err(e) => {
+            println!("[Rust] ERROR importing zip: {}", e);
+            FileListResult {
+                success: false,
+                files: vec![],
+                exists: None,
+                created: None,
+                skipped: None,
+                error: Some(e),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::paths::temp_dir;
+    use crate::types::Settings;
+    use std::fs;
+
+    #[test]
+    fn concurrent_saves_to_same_name_serialize() {
+        let dir = temp_dir("locks");
+        let trash_dir = dir.join(".trash");
+        let locks = FileLocks::default();
+        let history = TrashHistory::default();
+
+        // Each thread repeatedly writes its own distinct content; with the
+        // per-file lock the final file must be exactly one of them, never an
+        // interleaving.
+        let contents: Vec<String> = (0..4)
+            .map(|i| format!("// writer {}\n", i).repeat(200 * (i + 1)))
+            .collect();
+
+        let mut handles = Vec::new();
+        for content in &contents {
+            let locks = locks.clone();
+            let history = history.clone();
+            let dir = dir.clone();
+            let trash_dir = trash_dir.clone();
+            let content = content.clone();
+            handles.push(std::thread::spawn(move || {
+                for _ in 0..25 {
+                    save_cpp_file_locked(&locks, &history, &dir, &trash_dir, "shared.cpp", &content)
+                        .unwrap();
+                }
+            }));
+        }
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        let result = fs::read_to_string(dir.join("shared.cpp")).unwrap();
+        assert!(contents.contains(&result));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn scan_cpp_files_reports_file_in_the_way() {
+        let dir = temp_dir("genfile");
+        let bogus = dir.join("gen_cpp");
+        fs::write(&bogus, "not a directory").unwrap();
+
+        let result = scan_cpp_files(&bogus, false, &Settings::default().cpp_extensions, false, SortKey::Natural);
+        assert!(!result.success);
+        assert_eq!(
+            result.error.as_deref(),
+            Some("gen_cpp exists but is not a directory")
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn scan_cpp_files_matches_extensions_case_insensitively() {
+        let dir = temp_dir("extensions");
+        fs::write(dir.join("lower.cpp"), "").unwrap();
+        fs::write(dir.join("UPPER.CPP"), "").unwrap();
+        fs::write(dir.join("header.h"), "").unwrap();
+        fs::write(dir.join("readme.txt"), "").unwrap();
+
+        let result = scan_cpp_files(
+            &dir,
+            false,
+            &["cpp".to_string(), "h".to_string()],
+            false,
+            SortKey::Natural,
+        );
+        assert!(result.success);
+        // Natural sort is case-insensitive, so UPPER no longer sorts first
+        let names: Vec<&str> = result.files.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(names, vec!["header.h", "lower.cpp", "UPPER.CPP"]);
+        assert_eq!(result.files[0].extension, "h");
+        assert_eq!(result.files[1].extension, "cpp");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn scan_cpp_files_recurses_only_when_asked() {
+        let dir = temp_dir("recursive");
+        fs::write(dir.join("top.cpp"), "").unwrap();
+        fs::create_dir_all(dir.join("sub").join("inner")).unwrap();
+        fs::write(dir.join("sub").join("nested.cpp"), "").unwrap();
+        fs::write(dir.join("sub").join("inner").join("deep.cpp"), "").unwrap();
+
+        let exts = vec!["cpp".to_string()];
+        let flat = scan_cpp_files(&dir, false, &exts, false, SortKey::Natural);
+        let flat_names: Vec<&str> = flat.files.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(flat_names, vec!["top.cpp"]);
+
+        let deep = scan_cpp_files(&dir, false, &exts, true, SortKey::Natural);
+        let paths: Vec<&str> = deep.files.iter().map(|f| f.relative_path.as_str()).collect();
+        assert_eq!(paths, vec!["sub/inner/deep.cpp", "sub/nested.cpp", "top.cpp"]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn extension_stats_group_case_insensitively_with_a_bucket_for_none() {
+        let dir = temp_dir("extstats");
+        fs::create_dir_all(dir.join("sub")).unwrap();
+        fs::write(dir.join("a.cpp"), "12345").unwrap();
+        fs::write(dir.join("B.CPP"), "123").unwrap();
+        fs::write(dir.join("sub").join("c.cpp"), "1").unwrap();
+        fs::write(dir.join("util.h"), "12").unwrap();
+        fs::write(dir.join("Makefile"), "1234").unwrap();
+
+        let stats = extension_stats(&dir).unwrap();
+        let row = |ext: &str| stats.iter().find(|s| s.ext == ext).unwrap();
+        assert_eq!(row("cpp").count, 3);
+        assert_eq!(row("cpp").total_bytes, 9);
+        assert_eq!(row("h").count, 1);
+        assert_eq!(row("(no extension)").count, 1);
+        // Descending by count, so the cpp bucket leads
+        assert_eq!(stats[0].ext, "cpp");
+
+        // A missing directory yields an empty breakdown, not an error
+        assert!(extension_stats(&dir.join("nothere")).unwrap().is_empty());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn gz_files_round_trip_and_respect_the_size_cap() {
+        let dir = temp_dir("gz");
+        let locks = FileLocks::default();
+        let history = TrashHistory::default();
+        let extensions = vec!["cpp".to_string()];
+
+        save_cpp_file_locked(
+            &locks,
+            &history,
+            &dir,
+            &dir.join(".trash"),
+            "packed.cpp.gz",
+            "int main() {}\n",
+        )
+        .unwrap();
+        let on_disk = fs::read(dir.join("packed.cpp.gz")).unwrap();
+        // Really compressed, not plain text with a .gz name
+        assert_eq!(&on_disk[..2], &[0x1f, 0x8b]);
+
+        assert_eq!(
+            read_gz_to_string(&dir.join("packed.cpp.gz"), 1024).unwrap(),
+            "int main() {}\n"
+        );
+        // The cap applies to the decompressed size, not the on-disk size
+        let err = read_gz_to_string(&dir.join("packed.cpp.gz"), 4).unwrap_err();
+        assert!(err.contains("file too large after decompression"), "{}", err);
+
+        // The scan lists the compressed file tagged accordingly
+        let result = scan_cpp_files(&dir, false, &extensions, false, SortKey::Natural);
+        assert!(result.success);
+        let packed = result
+            .files
+            .iter()
+            .find(|f| f.name == "packed.cpp.gz")
+            .unwrap();
+        assert!(packed.compressed);
+        assert_eq!(packed.extension, "cpp");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn trash_preserves_content_and_restores_around_collisions() {
+        let dir = temp_dir("trash");
+        let gen_cpp = dir.join("gen_cpp");
+        let trash = dir.join(".trash");
+        fs::create_dir_all(&gen_cpp).unwrap();
+        let history = TrashHistory::default();
+
+        fs::write(gen_cpp.join("a.cpp"), "old content").unwrap();
+        trash_existing_file(&history, &trash, "a.cpp", &gen_cpp.join("a.cpp")).unwrap();
+        assert!(!gen_cpp.join("a.cpp").exists());
+
+        // The name was reused, so the restore target gets a suffix
+        fs::write(gen_cpp.join("a.cpp"), "newer content").unwrap();
+        assert_eq!(
+            restore_target(&gen_cpp, "a.cpp"),
+            gen_cpp.join("a.restored-1.cpp")
+        );
+
+        let entry = history.0.lock().unwrap().pop().unwrap();
+        let target = restore_target(&gen_cpp, &entry.filename);
+        fs::rename(&entry.trash_path, &target).unwrap();
+        assert_eq!(
+            fs::read_to_string(gen_cpp.join("a.restored-1.cpp")).unwrap(),
+            "old content"
+        );
+        assert_eq!(
+            fs::read_to_string(gen_cpp.join("a.cpp")).unwrap(),
+            "newer content"
+        );
+
+        // History is bounded: the oldest trash entry is pruned from disk
+        for i in 0..(TRASH_HISTORY_LIMIT + 2) {
+            let name = format!("f{}.cpp", i);
+            fs::write(gen_cpp.join(&name), "x").unwrap();
+            trash_existing_file(&history, &trash, &name, &gen_cpp.join(&name)).unwrap();
+        }
+        assert_eq!(history.0.lock().unwrap().len(), TRASH_HISTORY_LIMIT);
+        assert_eq!(fs::read_dir(&trash).unwrap().count(), TRASH_HISTORY_LIMIT);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn outline_finds_classes_structs_and_functions() {
+        let source = "\
+#include <vector>\n\
+// class NotThis\n\
+class Matrix {\n\
+public:\n\
+    Matrix();\n\
+};\n\
+class Forward;\n\
+struct Point { double x; double y; };\n\
+int add(int a, int b) {\n\
+    if (a > b) {\n\
+        return a + b;\n\
+    }\n\
+    return helper(a);\n\
+}\n\
+Matrix::Matrix()\n";
+
+        let items = outline_from_source(source);
+        let pairs: Vec<(&str, &str, u32)> = items
+            .iter()
+            .map(|i| (i.kind.as_str(), i.name.as_str(), i.line))
+            .collect();
+        assert_eq!(
+            pairs,
+            vec![
+                ("class", "Matrix", 3),
+                ("struct", "Point", 8),
+                ("function", "add", 9),
+                ("function", "Matrix", 15),
+            ]
+        );
+    }
+
+    #[test]
+    fn dry_run_clear_previews_without_deleting() {
+        let dir = temp_dir("dryclear");
+        let gen_cpp = dir.join("gen_cpp");
+        let extensions = Settings::default().cpp_extensions;
+        fs::create_dir_all(gen_cpp.join("module")).unwrap();
+        fs::write(gen_cpp.join("a.cpp"), "int main() {}").unwrap();
+        fs::write(gen_cpp.join("b.cpp"), "int main() {}").unwrap();
+        fs::write(gen_cpp.join("c.cpp.gz"), "compressed").unwrap();
+        fs::write(gen_cpp.join("keep.txt"), "notes").unwrap();
+
+        let preview = clear_gen_cpp_in(&gen_cpp, &extensions, false, true);
+        assert!(preview.success);
+        assert!(preview.dry_run);
+        assert_eq!(preview.deleted.len(), 3);
+        // Nothing was touched
+        assert!(gen_cpp.join("a.cpp").exists());
+        assert!(gen_cpp.join("b.cpp").exists());
+
+        let real = clear_gen_cpp_in(&gen_cpp, &extensions, false, false);
+        assert!(real.success);
+        assert!(!real.dry_run);
+        assert_eq!(real.deleted.len(), 3);
+        assert!(!gen_cpp.join("a.cpp").exists());
+        // Managed .cpp.gz files go too, not just bare .cpp
+        assert!(!gen_cpp.join("c.cpp.gz").exists());
+        assert!(gen_cpp.join("keep.txt").exists());
+        assert!(gen_cpp.join("module").is_dir());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn concurrent_scans_create_the_directory_without_spurious_failures() {
+        let dir = temp_dir("scanrace");
+        let gen_cpp = dir.join("gen_cpp");
+
+        // Every task races create_dir_all against the others; none may fail
+        let handles: Vec<_> = (0..16)
+            .map(|_| {
+                let gen_cpp = gen_cpp.clone();
+                std::thread::spawn(move || {
+                    scan_cpp_files(
+                        &gen_cpp,
+                        false,
+                        &Settings::default().cpp_extensions,
+                        false,
+                        SortKey::Natural,
+                    )
+                })
+            })
+            .collect();
+        for handle in handles {
+            let result = handle.join().unwrap();
+            assert!(result.success, "spurious failure: {:?}", result.error);
+        }
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn projects_split_live_and_stale_members() {
+        let dir = temp_dir("projects");
+        let gen_cpp = dir.join("gen_cpp");
+        fs::create_dir_all(&gen_cpp).unwrap();
+        fs::write(gen_cpp.join("kept.cpp"), "int main() {}").unwrap();
+
+        let project = project_from_entry(
+            &gen_cpp,
+            "demo",
+            &["kept.cpp".to_string(), "gone.cpp".to_string()],
+        );
+        assert_eq!(project.files, vec!["kept.cpp"]);
+        assert_eq!(project.stale, vec!["gone.cpp"]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn user_templates_are_listed_and_malformed_ones_skipped() {
+        let dir = temp_dir("templates");
+        // Metadata from a companion json
+        fs::write(dir.join("matrix.cpp"), "int m;").unwrap();
+        fs::write(
+            dir.join("matrix.json"),
+            r#"{"name": "Matrix", "description": "Dense matrix scaffold"}"#,
+        )
+        .unwrap();
+        // Metadata from the leading comment block
+        fs::write(
+            dir.join("solver.cpp"),
+            "// Solver
+// Iterative solver skeleton
+int s;",
+        )
+        .unwrap();
+        // Invalid json and a template with no metadata at all: both skipped
+        fs::write(dir.join("broken.cpp"), "int b;").unwrap();
+        fs::write(dir.join("broken.json"), "{not json").unwrap();
+        fs::write(dir.join("bare.cpp"), "int x;").unwrap();
+
+        let templates = collect_user_templates(&dir);
+        let ids: Vec<&str> = templates.iter().map(|t| t.id.as_str()).collect();
+        assert_eq!(ids, vec!["matrix", "solver"]);
+        assert_eq!(templates[0].name, "Matrix");
+        assert_eq!(templates[0].description, "Dense matrix scaffold");
+        assert_eq!(templates[1].name, "Solver");
+        assert_eq!(templates[1].description, "Iterative solver skeleton");
+
+        // No template directory at all is simply an empty list
+        assert!(collect_user_templates(&dir.join("missing")).is_empty());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn moves_verify_the_copy_before_deleting_the_source() {
+        let dir = temp_dir("safemove");
+        fs::write(dir.join("src.cpp"), "precious content").unwrap();
+
+        // The common case: rename (or verified copy) succeeds and the
+        // source is gone
+        move_file(&dir.join("src.cpp"), &dir.join("dst.cpp")).unwrap();
+        assert!(!dir.join("src.cpp").exists());
+        assert_eq!(
+            fs::read_to_string(dir.join("dst.cpp")).unwrap(),
+            "precious content"
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn dropped_files_import_to_the_right_places_with_suffixes() {
+        let dir = temp_dir("drop");
+        let incoming = dir.join("incoming");
+        fs::create_dir_all(&incoming).unwrap();
+        fs::write(incoming.join("math.cpp"), "int x;").unwrap();
+        fs::write(incoming.join("glue.js"), "// glue").unwrap();
+        fs::write(incoming.join("notes.txt"), "nope").unwrap();
+        // Pre-existing file forces the collision suffix
+        fs::create_dir_all(dir.join("gen_cpp")).unwrap();
+        fs::write(dir.join("gen_cpp").join("math.cpp"), "old").unwrap();
+
+        let paths = vec![
+            incoming.join("math.cpp"),
+            incoming.join("glue.js"),
+            incoming.join("notes.txt"),
+        ];
+        let results = import_dropped_files(&dir, "dropped", &paths);
+
+        assert!(results[0].success);
+        assert!(results[0].target.ends_with("math-1.cpp"));
+        assert!(dir.join("gen_cpp").join("math-1.cpp").exists());
+        assert!(results[1].success);
+        assert!(dir.join("trove").join("dropped").join("glue.js").exists());
+        assert!(!results[2].success);
+        assert_eq!(results[2].error.as_deref(), Some("Unsupported file type"));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,1192 @@
+//! Generic file commands: open/save/stream, native dialogs, watchers, log
+//! tailing, and disk space.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+use tauri::Manager;
+
+use crate::commands::{with_timeout, CancelFlags, CANCELLED_MSG};
+use crate::events::{emit_event, AppEvent};
+use crate::paths::{expand_tilde, madola_base, resolve_existing_path, resolve_target_path};
+use crate::types::{load_settings, DiskSpace, DroppedPath, FileChunk, FileContentResult, SaveResult};
+
+// Active single-file watchers keyed by watched path. Dropping a watcher
+// stops its event delivery, so removal from the map is the whole teardown.
+#[derive(Default)]
+pub struct FileWatchers(Mutex<HashMap<String, notify::RecommendedWatcher>>);
+
+impl FileWatchers {
+    // Drop every watcher; used by shutdown
+    pub fn clear(&self) {
+        self.0.lock().unwrap().clear();
+    }
+}
+
+// Watch a single file and notify the frontend when it changes on disk, so
+// the editor can offer a reload instead of showing stale content
+#[tauri::command]
+pub async fn watch_file(
+    window: tauri::Window,
+    path: String,
+    watchers: tauri::State<'_, FileWatchers>,
+) -> Result<(), String> {
+    println!("[Rust] watch_file called: {}", path);
+
+    let mut map = watchers.0.lock().unwrap();
+    if map.contains_key(&path) {
+        // Re-watching the same path must not stack duplicate watchers
+        return Ok(());
+    }
+
+    use notify::Watcher;
+    let emit_path = path.clone();
+    let mut watcher = notify::recommended_watcher(move |result: Result<notify::Event, notify::Error>| {
+        if let Ok(event) = result {
+            if matches!(
+                event.kind,
+                notify::EventKind::Modify(_)
+                    | notify::EventKind::Create(_)
+                    | notify::EventKind::Remove(_)
+            ) {
+                let _ = emit_event(&window, AppEvent::FileExternallyModified(emit_path.clone()));
+            }
+        }
+    })
+    .map_err(|e| format!("Failed to create watcher: {}", e))?;
+
+    watcher
+        .watch(Path::new(&path), notify::RecursiveMode::NonRecursive)
+        .map_err(|e| format!("Failed to watch file: {}", e))?;
+    map.insert(path, watcher);
+    Ok(())
+}
+
+// Stop watching a file previously registered with watch_file
+#[tauri::command]
+pub async fn unwatch_file(
+    path: String,
+    watchers: tauri::State<'_, FileWatchers>,
+) -> Result<(), String> {
+    println!("[Rust] unwatch_file called: {}", path);
+
+    use notify::Watcher;
+    let mut map = watchers.0.lock().unwrap();
+    if let Some(mut watcher) = map.remove(&path) {
+        let _ = watcher.unwatch(Path::new(&path));
+    }
+    Ok(())
+}
+
+// Stat each dropped path once so the frontend can act on the drop without
+// round-tripping. A path that vanished between the drop and the stat is
+// kept with exists: false rather than silently omitted.
+pub fn describe_dropped_paths(paths: &[PathBuf]) -> Vec<DroppedPath> {
+    paths
+        .iter()
+        .map(|path| {
+            let metadata = fs::metadata(path);
+            let exists = metadata.is_ok();
+            let (is_dir, size) = metadata
+                .map(|m| (m.is_dir(), if m.is_dir() { 0 } else { m.len() }))
+                .unwrap_or((false, 0));
+            let ext = if is_dir {
+                String::new()
+            } else {
+                path.extension()
+                    .map(|e| e.to_string_lossy().to_lowercase())
+                    .unwrap_or_default()
+            };
+            DroppedPath {
+                path: path.to_string_lossy().to_string(),
+                is_dir,
+                size,
+                ext,
+                exists,
+            }
+        })
+        .collect()
+}
+
+// Watches gen_cpp and trove for the file/module browsers. Native notify
+// events when the backend works; otherwise (or when forced via the
+// poll_watch setting) a polling loop compares cheap directory signatures.
+#[derive(Default)]
+pub struct DirWatch {
+    watcher: Mutex<Option<notify::RecommendedWatcher>>,
+    // Bumped on every start/stop; a running poll loop exits once it no
+    // longer matches
+    poll_generation: Arc<Mutex<u64>>,
+}
+
+impl DirWatch {
+    // Tear down whichever mode is active (native watcher or poll loop)
+    pub fn stop(&self) {
+        *self.watcher.lock().unwrap() = None;
+        *self.poll_generation.lock().unwrap() += 1;
+    }
+}
+
+// Cheap change signature for a directory tree: entry count, newest mtime,
+// and total size. Misses a same-size in-place rewrite within one mtime
+// granule, which is acceptable for a refresh hint.
+fn dir_signature(dir: &Path) -> (u64, Option<SystemTime>, u64) {
+    let mut count = 0u64;
+    let mut newest: Option<SystemTime> = None;
+    let mut total = 0u64;
+    let mut stack = vec![dir.to_path_buf()];
+    while let Some(current) = stack.pop() {
+        if let Ok(entries) = fs::read_dir(&current) {
+            for entry in entries.flatten() {
+                count += 1;
+                let path = entry.path();
+                if path.is_dir() {
+                    stack.push(path);
+                } else if let Ok(metadata) = entry.metadata() {
+                    total += metadata.len();
+                    if let Ok(modified) = metadata.modified() {
+                        newest = std::cmp::max(newest, Some(modified));
+                    }
+                }
+            }
+        }
+    }
+    (count, newest, total)
+}
+
+// Try to register a native recursive watch on gen_cpp and trove, emitting
+// "gen-cpp-changed" / "trove-changed" on any event under each
+fn native_dir_watch(
+    window: tauri::Window,
+    gen_cpp_dir: &Path,
+    trove_dir: &Path,
+) -> Result<notify::RecommendedWatcher, String> {
+    use notify::Watcher;
+
+    let gen_cpp = gen_cpp_dir.to_path_buf();
+    let mut watcher =
+        notify::recommended_watcher(move |result: Result<notify::Event, notify::Error>| {
+            if let Ok(event) = result {
+                if matches!(
+                    event.kind,
+                    notify::EventKind::Modify(_)
+                        | notify::EventKind::Create(_)
+                        | notify::EventKind::Remove(_)
+                ) {
+                    let in_gen_cpp = event.paths.iter().any(|p| p.starts_with(&gen_cpp));
+                    let changed = if in_gen_cpp {
+                        AppEvent::GenCppChanged
+                    } else {
+                        AppEvent::TroveChanged
+                    };
+                    let _ = emit_event(&window, changed);
+                }
+            }
+        })
+        .map_err(|e| format!("Failed to create watcher: {}", e))?;
+    watcher
+        .watch(gen_cpp_dir, notify::RecursiveMode::Recursive)
+        .map_err(|e| format!("Failed to watch gen_cpp: {}", e))?;
+    watcher
+        .watch(trove_dir, notify::RecursiveMode::Recursive)
+        .map_err(|e| format!("Failed to watch trove: {}", e))?;
+    Ok(watcher)
+}
+
+// Start watching gen_cpp and trove; returns "native" or "polling" so the
+// frontend can surface which mode is active
+#[tauri::command]
+pub async fn start_dir_watch(
+    window: tauri::Window,
+    state: tauri::State<'_, DirWatch>,
+) -> Result<String, String> {
+    println!("[Rust] start_dir_watch called");
+
+    let base = madola_base()?;
+    let gen_cpp_dir = base.join("gen_cpp");
+    let trove_dir = base.join("trove");
+    for dir in [&gen_cpp_dir, &trove_dir] {
+        fs::create_dir_all(dir).map_err(|e| format!("Failed to create {:?}: {}", dir, e))?;
+    }
+
+    // Tear down whatever mode was running before
+    state.stop();
+
+    let settings = load_settings();
+    if !settings.poll_watch {
+        match native_dir_watch(window.clone(), &gen_cpp_dir, &trove_dir) {
+            Ok(watcher) => {
+                *state.watcher.lock().unwrap() = Some(watcher);
+                return Ok("native".to_string());
+            }
+            Err(e) => {
+                // e.g. inotify exhaustion or an unsupported filesystem;
+                // fall back to polling rather than silently not refreshing
+                println!("[Rust] WARNING: native watch unavailable ({}), polling", e);
+            }
+        }
+    }
+
+    let generation = {
+        let mut current = state.poll_generation.lock().unwrap();
+        *current += 1;
+        *current
+    };
+    let generations = state.poll_generation.clone();
+    let interval = settings.poll_interval_secs.max(1);
+    tauri::async_runtime::spawn(async move {
+        let mut gen_cpp_sig = dir_signature(&gen_cpp_dir);
+        let mut trove_sig = dir_signature(&trove_dir);
+        loop {
+            tokio::time::sleep(std::time::Duration::from_secs(interval)).await;
+            if *generations.lock().unwrap() != generation {
+                break;
+            }
+            let next = dir_signature(&gen_cpp_dir);
+            if next != gen_cpp_sig {
+                gen_cpp_sig = next;
+                let _ = emit_event(&window, AppEvent::GenCppChanged);
+            }
+            let next = dir_signature(&trove_dir);
+            if next != trove_sig {
+                trove_sig = next;
+                let _ = emit_event(&window, AppEvent::TroveChanged);
+            }
+        }
+    });
+    Ok("polling".to_string())
+}
+
+// Stop directory watching in whichever mode is active
+#[tauri::command]
+pub async fn stop_dir_watch(state: tauri::State<'_, DirWatch>) -> Result<(), String> {
+    println!("[Rust] stop_dir_watch called");
+    state.stop();
+    Ok(())
+}
+
+// Live log streaming: holds the notify watcher while a stream is active.
+// Dropping the watcher stops event delivery, as with FileWatchers.
+#[derive(Default)]
+pub struct LogStream(Mutex<Option<notify::RecommendedWatcher>>);
+
+impl LogStream {
+    pub fn stop(&self) {
+        *self.0.lock().unwrap() = None;
+    }
+}
+
+// Block size for reading a log backwards from the end
+const TAIL_BLOCK_BYTES: u64 = 8192;
+
+// Last `lines` lines of a file, read backwards in blocks so a large log is
+// never loaded whole
+fn tail_lines(path: &Path, lines: usize) -> Result<Vec<String>, String> {
+    use std::io::{Read, Seek, SeekFrom};
+
+    let mut file = fs::File::open(path)
+        .map_err(|e| format!("Failed to open log: {}", e))?;
+    let len = file
+        .metadata()
+        .map_err(|e| format!("Failed to stat log: {}", e))?
+        .len();
+
+    let mut buf: Vec<u8> = Vec::new();
+    let mut pos = len;
+    while pos > 0 && buf.iter().filter(|&&b| b == b'\n').count() <= lines {
+        let read_len = TAIL_BLOCK_BYTES.min(pos);
+        pos -= read_len;
+        file.seek(SeekFrom::Start(pos))
+            .map_err(|e| format!("Failed to seek log: {}", e))?;
+        let mut block = vec![0u8; read_len as usize];
+        file.read_exact(&mut block)
+            .map_err(|e| format!("Failed to read log: {}", e))?;
+        block.extend_from_slice(&buf);
+        buf = block;
+    }
+
+    let text = String::from_utf8_lossy(&buf);
+    let mut collected: Vec<String> = text.lines().map(|l| l.to_string()).collect();
+    if collected.len() > lines {
+        collected.drain(..collected.len() - lines);
+    }
+    Ok(collected)
+}
+
+// Last N lines of ~/.madola/madola.log
+#[tauri::command]
+pub async fn tail_log(lines: usize) -> Result<Vec<String>, String> {
+    println!("[Rust] tail_log called: {} lines", lines);
+    with_timeout(move || {
+        let path = madola_base()?.join("madola.log");
+        tail_lines(&path, lines)
+    })
+    .await?
+}
+
+// Emit every line appended since the last delivered offset. A file shorter
+// than the offset means the log was truncated or rotated, so re-read from
+// the start.
+fn emit_new_log_lines(window: &tauri::Window, path: &Path, offset: &mut u64) {
+    use std::io::{BufRead, BufReader, Seek, SeekFrom};
+
+    let file = match fs::File::open(path) {
+        Ok(file) => file,
+        Err(_) => return,
+    };
+    let len = file.metadata().map(|m| m.len()).unwrap_or(0);
+    if len < *offset {
+        *offset = 0;
+    }
+    let mut reader = BufReader::new(file);
+    if reader.seek(SeekFrom::Start(*offset)).is_err() {
+        return;
+    }
+    let mut line = String::new();
+    loop {
+        line.clear();
+        match reader.read_line(&mut line) {
+            Ok(0) | Err(_) => break,
+            Ok(n) => {
+                // A tail without a newline is a line still being written;
+                // leave it (and the offset) for the next wakeup so it
+                // arrives whole instead of split across two events
+                if !line.ends_with('\n') {
+                    break;
+                }
+                *offset += n as u64;
+                let _ = emit_event(
+                    window,
+                    AppEvent::LogLine(line.trim_end_matches(['\r', '\n']).to_string()),
+                );
+            }
+        }
+    }
+}
+
+// Start emitting "log-line" events as ~/.madola/madola.log grows. Watching
+// the parent directory keeps rotation visible even when the inode changes.
+#[tauri::command]
+pub async fn start_log_stream(
+    window: tauri::Window,
+    stream: tauri::State<'_, LogStream>,
+) -> Result<(), String> {
+    println!("[Rust] start_log_stream called");
+
+    let mut slot = stream.0.lock().unwrap();
+    if slot.is_some() {
+        // Starting twice must not stack duplicate watchers
+        return Ok(());
+    }
+
+    let path = madola_base()?.join("madola.log");
+    // Existing content belongs to tail_log; the stream starts at the end
+    let start = fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+    let offset = Arc::new(Mutex::new(start));
+
+    use notify::Watcher;
+    let log_path = path.clone();
+    let mut watcher = notify::recommended_watcher(move |result: Result<notify::Event, notify::Error>| {
+        if let Ok(event) = result {
+            let touches_log = event.paths.iter().any(|p| p.ends_with("madola.log"));
+            if touches_log
+                && matches!(
+                    event.kind,
+                    notify::EventKind::Modify(_) | notify::EventKind::Create(_)
+                )
+            {
+                let mut offset = offset.lock().unwrap();
+                emit_new_log_lines(&window, &log_path, &mut offset);
+            }
+        }
+    })
+    .map_err(|e| format!("Failed to create watcher: {}", e))?;
+
+    let watch_target = path
+        .parent()
+        .map(|p| p.to_path_buf())
+        .unwrap_or_else(|| path.clone());
+    fs::create_dir_all(&watch_target)
+        .map_err(|e| format!("Failed to create directory: {}", e))?;
+    watcher
+        .watch(&watch_target, notify::RecursiveMode::NonRecursive)
+        .map_err(|e| format!("Failed to watch log: {}", e))?;
+    *slot = Some(watcher);
+    Ok(())
+}
+
+// Stop a stream previously started with start_log_stream
+#[tauri::command]
+pub async fn stop_log_stream(stream: tauri::State<'_, LogStream>) -> Result<(), String> {
+    println!("[Rust] stop_log_stream called");
+    *stream.0.lock().unwrap() = None;
+    Ok(())
+}
+
+// Lines are newline-separated, with an implicit final line when the file
+// doesn't end in a newline; characters are counted as chars so multibyte
+// text is handled correctly
+pub fn count_lines_chars(content: &str) -> (u32, u32) {
+    let chars = content.chars().count() as u32;
+    let mut lines = content.matches('\n').count() as u32;
+    if !content.is_empty() && !content.ends_with('\n') {
+        lines += 1;
+    }
+    (lines, chars)
+}
+
+// Refuse to load a file bigger than the configured limit; stat first so we
+// never pull a multi-gigabyte file into a String
+pub fn check_file_size(path: &Path, limit: u64) -> Result<(), String> {
+    let metadata = fs::metadata(path)
+        .map_err(|e| format!("Failed to stat file: {}", e))?;
+    if metadata.len() > limit {
+        return Err(format!(
+            "file too large ({} bytes, limit {})",
+            metadata.len(),
+            limit
+        ));
+    }
+    Ok(())
+}
+
+// Detect the dominant line-ending style of a file's content
+pub fn detect_line_ending(content: &str) -> &'static str {
+    let crlf = content.matches("\r\n").count();
+    let lf_only = content.matches('\n').count() - crlf;
+    if crlf > 0 && lf_only > 0 {
+        "mixed"
+    } else if crlf > 0 {
+        "crlf"
+    } else {
+        "lf"
+    }
+}
+
+// Strip a leading UTF-8 BOM, reporting whether one was present
+pub fn strip_bom(content: String) -> (String, bool) {
+    match content.strip_prefix('\u{feff}') {
+        Some(stripped) => (stripped.to_string(), true),
+        None => (content, false),
+    }
+}
+
+// Apply the caller's preserved style before writing: re-normalize line
+// endings to the hinted flavor and re-add the BOM if requested
+pub fn apply_save_style(content: String, line_ending: Option<&str>, add_bom: bool) -> String {
+    let mut out = match line_ending {
+        Some("crlf") => content.replace("\r\n", "\n").replace('\n', "\r\n"),
+        Some("lf") => content.replace("\r\n", "\n"),
+        _ => content,
+    };
+    if add_bom && !out.starts_with('\u{feff}') {
+        out.insert(0, '\u{feff}');
+    }
+    out
+}
+
+// File operations. These return the same structured result shapes as the
+// file-browser commands so the frontend has a single error-handling path.
+// All filesystem work runs on the blocking pool (via with_timeout) because
+// std::fs calls on the async executor would stall every other command
+// sharing its threads.
+#[tauri::command]
+pub async fn open_file(path: String) -> FileContentResult {
+    match with_timeout(move || open_file_blocking(path)).await {
+        Ok(result) => result,
+        Err(e) => FileContentResult {
+            success: false,
+            error: Some(e),
+            ..Default::default()
+        },
+    }
+}
+
+fn open_file_blocking(path: String) -> FileContentResult {
+    let fail = |error: String| FileContentResult {
+        success: false,
+        error: Some(error),
+        ..Default::default()
+    };
+
+    // Stat up front so a missing or directory path gets a dedicated message
+    // instead of the platform-specific read error
+    let expanded = match expand_tilde(&path) {
+        Ok(expanded) => expanded,
+        Err(e) => return fail(e),
+    };
+    if !expanded.exists() {
+        return fail("path does not exist".to_string());
+    }
+    let path = match resolve_existing_path(&path) {
+        Ok(resolved) => resolved,
+        Err(e) => return fail(e),
+    };
+    if path.is_dir() {
+        return fail("path is a directory, not a file".to_string());
+    }
+    let filename = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .map(|s| s.to_string());
+
+    if let Err(e) = check_file_size(&path, load_settings().max_file_size_bytes) {
+        return FileContentResult {
+            success: false,
+            error: Some(e),
+            ..Default::default()
+        };
+    }
+
+    match fs::read_to_string(&path) {
+        Ok(content) => {
+            let (content, has_bom) = strip_bom(content);
+            let line_ending = detect_line_ending(&content).to_string();
+            let (line_count, char_count) = count_lines_chars(&content);
+            FileContentResult {
+                success: true,
+                content: Some(content),
+                filename,
+                has_bom: Some(has_bom),
+                line_ending: Some(line_ending),
+                line_count: Some(line_count),
+                char_count: Some(char_count),
+                error: None,
+            }
+        }
+        Err(e) => FileContentResult {
+            success: false,
+            content: None,
+            filename: None,
+            has_bom: None,
+            line_ending: None,
+            line_count: None,
+            char_count: None,
+            error: Some(format!("Failed to read file: {}", e)),
+        },
+    }
+}
+
+#[tauri::command]
+pub async fn save_file(
+    path: String,
+    content: String,
+    line_ending: Option<String>,
+    add_bom: Option<bool>,
+    create_dirs: Option<bool>,
+) -> SaveResult {
+    let write = move || save_file_blocking(path, content, line_ending, add_bom, create_dirs);
+    match with_timeout(write).await {
+        Ok(result) => result,
+        Err(e) => SaveResult {
+            success: false,
+            bytes_written: 0,
+            error: Some(e),
+        },
+    }
+}
+
+fn save_file_blocking(
+    path: String,
+    content: String,
+    line_ending: Option<String>,
+    add_bom: Option<bool>,
+    create_dirs: Option<bool>,
+) -> SaveResult {
+    let fail = |error: String| SaveResult {
+        success: false,
+        bytes_written: 0,
+        error: Some(error),
+    };
+
+    // Missing parent directories are only created when the caller opts in;
+    // otherwise fail with something clearer than the raw OS error
+    let expanded = match expand_tilde(&path) {
+        Ok(expanded) => expanded,
+        Err(e) => return fail(e),
+    };
+    if let Some(parent) = expanded.parent() {
+        if !parent.as_os_str().is_empty() && !parent.exists() {
+            if create_dirs.unwrap_or(false) {
+                if let Err(e) = fs::create_dir_all(parent) {
+                    return fail(format!("Failed to create directory: {}", e));
+                }
+            } else {
+                return fail("parent directory does not exist".to_string());
+            }
+        }
+    }
+
+    let path = match resolve_target_path(&path) {
+        Ok(resolved) => resolved,
+        Err(e) => return fail(e),
+    };
+    let content = apply_save_style(content, line_ending.as_deref(), add_bom.unwrap_or(false));
+    let bytes = content.len() as u64;
+    match fs::write(&path, content) {
+        Ok(()) => SaveResult {
+            success: true,
+            bytes_written: bytes,
+            error: None,
+        },
+        Err(e) => SaveResult {
+            success: false,
+            bytes_written: 0,
+            error: Some(format!("Failed to write file: {}", e)),
+        },
+    }
+}
+
+#[tauri::command]
+pub async fn get_file_name(path: String) -> Result<String, String> {
+    let path_buf = PathBuf::from(path);
+    path_buf
+        .file_name()
+        .and_then(|n| n.to_str())
+        .map(|s| s.to_string())
+        .ok_or_else(|| "Failed to get file name".to_string())
+}
+
+// Synchronous body of stream_file. Chunks are cut at UTF-8 boundaries: a
+// multibyte character split across reads is carried over to the next chunk
+// instead of being mangled.
+fn stream_file_blocking(
+    window: &tauri::Window,
+    path: &Path,
+    chunk_size: usize,
+    cancel: &AtomicBool,
+) -> Result<u32, String> {
+    use std::io::Read;
+
+    let mut file = fs::File::open(path)
+        .map_err(|e| format!("Failed to open file: {}", e))?;
+    let mut buffer = vec![0u8; chunk_size];
+    let mut pending: Vec<u8> = Vec::new();
+    let mut seq: u32 = 0;
+
+    loop {
+        // Checked between chunks, like the other cancellable commands
+        if cancel.load(Ordering::Relaxed) {
+            return Err(CANCELLED_MSG.to_string());
+        }
+        let n = file
+            .read(&mut buffer)
+            .map_err(|e| format!("Failed to read file: {}", e))?;
+        if n == 0 {
+            break;
+        }
+        pending.extend_from_slice(&buffer[..n]);
+        let valid_up_to = match std::str::from_utf8(&pending) {
+            Ok(_) => pending.len(),
+            Err(e) => e.valid_up_to(),
+        };
+        let ready: Vec<u8> = pending.drain(..valid_up_to).collect();
+        // A leftover tail longer than one code point cannot complete later
+        if pending.len() >= 4 {
+            return Err("file is not valid UTF-8".to_string());
+        }
+        if !ready.is_empty() {
+            let data = String::from_utf8(ready)
+                .map_err(|_| "file is not valid UTF-8".to_string())?;
+            emit_event(
+                window,
+                AppEvent::FileChunk(FileChunk {
+                    seq,
+                    data,
+                    eof: false,
+                }),
+            )?;
+            seq += 1;
+        }
+    }
+    if !pending.is_empty() {
+        return Err("file is not valid UTF-8".to_string());
+    }
+    emit_event(
+        window,
+        AppEvent::FileChunk(FileChunk {
+            seq,
+            data: String::new(),
+            eof: true,
+        }),
+    )?;
+    Ok(seq + 1)
+}
+
+// Stream a file to the frontend as "file-chunk" events, for files too large
+// to return as one IPC string. The final event has eof: true and empty
+// data; the return value is the number of chunks emitted. The max-size
+// setting only applies when enforce_limit is set, since streaming is the
+// mechanism of choice for large files.
+#[tauri::command]
+pub async fn stream_file(
+    window: tauri::Window,
+    path: String,
+    chunk_size: usize,
+    enforce_limit: Option<bool>,
+    op_id: Option<String>,
+) -> Result<u32, String> {
+    println!("[Rust] stream_file called: {} ({} byte chunks)", path, chunk_size);
+    if chunk_size == 0 {
+        return Err("chunk_size must be positive".to_string());
+    }
+
+    // Validate before registering, so an early error return can't leave
+    // the op_id entry behind with no finish to reclaim it
+    let resolved = resolve_existing_path(&path)?;
+    if enforce_limit.unwrap_or(false) {
+        check_file_size(&resolved, load_settings().max_file_size_bytes)?;
+    }
+
+    let cancel = window.state::<CancelFlags>().register(&op_id);
+    let worker_window = window.clone();
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        stream_file_blocking(&worker_window, &resolved, chunk_size, &cancel)
+    })
+    .await
+    .map_err(|e| format!("Blocking task failed: {}", e))
+    .and_then(|result| result);
+    window.state::<CancelFlags>().finish(&op_id);
+    result
+}
+
+// Cached newline offsets per file, valid only for the recorded mtime, so
+// repeated paging over a large read-only file skips the scan
+#[derive(Default)]
+pub struct LineIndexCache(Mutex<HashMap<String, CachedLineIndex>>);
+
+// Line-start offsets for one file, keyed by the mtime they were built at
+type CachedLineIndex = (SystemTime, Arc<Vec<u64>>);
+
+impl LineIndexCache {
+    fn cached(&self, key: &str, mtime: SystemTime) -> Option<Arc<Vec<u64>>> {
+        self.0
+            .lock()
+            .unwrap()
+            .get(key)
+            .filter(|(cached_mtime, _)| *cached_mtime == mtime)
+            .map(|(_, index)| index.clone())
+    }
+
+    fn store(&self, key: &str, mtime: SystemTime, index: Arc<Vec<u64>>) {
+        self.0
+            .lock()
+            .unwrap()
+            .insert(key.to_string(), (mtime, index));
+    }
+}
+
+// Byte offsets where each line starts, found by scanning for newlines in
+// chunks; the whole file is never held in memory
+fn build_line_index(path: &Path) -> Result<Vec<u64>, String> {
+    use std::io::Read;
+
+    let mut file = fs::File::open(path)
+        .map_err(|e| format!("Failed to open file: {}", e))?;
+    let mut starts: Vec<u64> = vec![0];
+    let mut buffer = [0u8; 64 * 1024];
+    let mut offset: u64 = 0;
+    loop {
+        let n = file
+            .read(&mut buffer)
+            .map_err(|e| format!("Failed to read file: {}", e))?;
+        if n == 0 {
+            break;
+        }
+        for (i, byte) in buffer[..n].iter().enumerate() {
+            if *byte == b'\n' {
+                starts.push(offset + i as u64 + 1);
+            }
+        }
+        offset += n as u64;
+    }
+    Ok(starts)
+}
+
+// Read only the bytes covering lines [start_line, start_line + count),
+// using the precomputed line starts. Requests past the end return an empty
+// window rather than an error, which is what a virtualized list expects.
+fn read_line_window(
+    path: &Path,
+    starts: &[u64],
+    file_len: u64,
+    start_line: usize,
+    count: usize,
+) -> Result<Vec<String>, String> {
+    use std::io::{Read, Seek, SeekFrom};
+
+    // Entries equal to file_len are the phantom start after a trailing
+    // newline, not a real line
+    if start_line >= starts.len() || starts[start_line] >= file_len {
+        return Ok(vec![]);
+    }
+    let begin = starts[start_line];
+    let end = starts
+        .get(start_line + count)
+        .copied()
+        .unwrap_or(file_len)
+        .min(file_len);
+
+    let mut file = fs::File::open(path)
+        .map_err(|e| format!("Failed to open file: {}", e))?;
+    file.seek(SeekFrom::Start(begin))
+        .map_err(|e| format!("Failed to seek file: {}", e))?;
+    let mut bytes = vec![0u8; (end - begin) as usize];
+    file.read_exact(&mut bytes)
+        .map_err(|e| format!("Failed to read file: {}", e))?;
+    Ok(String::from_utf8_lossy(&bytes)
+        .lines()
+        .map(|line| line.to_string())
+        .collect())
+}
+
+// Read-only viewer: a window of lines from a large file without loading it
+// whole. The line index is cached per path+mtime, so paging after the first
+// call costs one small read.
+#[tauri::command]
+pub async fn open_file_readonly_lines(
+    path: String,
+    start_line: usize,
+    count: usize,
+    cache: tauri::State<'_, LineIndexCache>,
+) -> Result<Vec<String>, String> {
+    let resolved = resolve_existing_path(&path)?;
+    if resolved.is_dir() {
+        return Err("path is a directory, not a file".to_string());
+    }
+    let metadata = fs::metadata(&resolved)
+        .map_err(|e| format!("Failed to stat file: {}", e))?;
+    let mtime = metadata
+        .modified()
+        .map_err(|e| format!("Failed to stat file: {}", e))?;
+    let file_len = metadata.len();
+    let key = resolved.to_string_lossy().to_string();
+
+    let index = match cache.cached(&key, mtime) {
+        Some(index) => index,
+        None => {
+            let index_path = resolved.clone();
+            let index = Arc::new(with_timeout(move || build_line_index(&index_path)).await??);
+            cache.store(&key, mtime, index.clone());
+            index
+        }
+    };
+
+    with_timeout(move || read_line_window(&resolved, &index, file_len, start_line, count)).await?
+}
+
+// Hex SHA-256 of a file, streamed in chunks so large files are never held in
+// memory at once
+pub fn hash_file_streaming(path: &Path) -> Result<String, String> {
+    use sha2::{Digest, Sha256};
+    use std::io::Read;
+
+    let mut file = fs::File::open(path)
+        .map_err(|e| format!("Failed to open file: {}", e))?;
+    let mut hasher = Sha256::new();
+    let mut buffer = [0u8; 64 * 1024];
+    loop {
+        let n = file.read(&mut buffer)
+            .map_err(|e| format!("Failed to read file: {}", e))?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buffer[..n]);
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+// Build a native file dialog with the caller's (name, extensions) filters
+fn file_dialog_with_filters(
+    filters: &[(String, Vec<String>)],
+) -> tauri::api::dialog::blocking::FileDialogBuilder {
+    let mut builder = tauri::api::dialog::blocking::FileDialogBuilder::new();
+    for (name, extensions) in filters {
+        let extension_refs: Vec<&str> = extensions.iter().map(|s| s.as_str()).collect();
+        builder = builder.add_filter(name, &extension_refs);
+    }
+    builder
+}
+
+// Native open dialog; returns the chosen absolute path, or None on cancel
+#[tauri::command]
+pub async fn pick_file(filters: Vec<(String, Vec<String>)>) -> Option<String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        file_dialog_with_filters(&filters)
+            .pick_file()
+            .map(|path| path.to_string_lossy().to_string())
+    })
+    .await
+    .ok()
+    .flatten()
+}
+
+// Native save dialog; returns the chosen absolute path, or None on cancel
+#[tauri::command]
+pub async fn pick_save_path(
+    filters: Vec<(String, Vec<String>)>,
+    default_name: Option<String>,
+) -> Option<String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let mut builder = file_dialog_with_filters(&filters);
+        if let Some(name) = &default_name {
+            builder = builder.set_file_name(name);
+        }
+        builder
+            .save_file()
+            .map(|path| path.to_string_lossy().to_string())
+    })
+    .await
+    .ok()
+    .flatten()
+}
+
+// Free/total space on the filesystem containing the given path (statvfs shim)
+#[cfg(unix)]
+#[allow(clippy::unnecessary_cast)] // statvfs field widths vary by platform
+fn disk_space_for(path: &Path) -> Result<DiskSpace, String> {
+    use std::os::unix::ffi::OsStrExt;
+
+    let cpath = std::ffi::CString::new(path.as_os_str().as_bytes())
+        .map_err(|_| "Path contains a NUL byte".to_string())?;
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    if unsafe { libc::statvfs(cpath.as_ptr(), &mut stat) } != 0 {
+        return Err(format!(
+            "statvfs failed: {}",
+            std::io::Error::last_os_error()
+        ));
+    }
+    Ok(DiskSpace {
+        total_bytes: stat.f_blocks as u64 * stat.f_frsize as u64,
+        available_bytes: stat.f_bavail as u64 * stat.f_frsize as u64,
+    })
+}
+
+// Free/total space on the filesystem containing the given path (Win32 shim)
+#[cfg(windows)]
+fn disk_space_for(path: &Path) -> Result<DiskSpace, String> {
+    use std::os::windows::ffi::OsStrExt;
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GetDiskFreeSpaceExW(
+            directory_name: *const u16,
+            free_bytes_available: *mut u64,
+            total_number_of_bytes: *mut u64,
+            total_number_of_free_bytes: *mut u64,
+        ) -> i32;
+    }
+
+    let wide: Vec<u16> = path
+        .as_os_str()
+        .encode_wide()
+        .chain(std::iter::once(0))
+        .collect();
+    let mut available = 0u64;
+    let mut total = 0u64;
+    let mut free = 0u64;
+    let ok = unsafe { GetDiskFreeSpaceExW(wide.as_ptr(), &mut available, &mut total, &mut free) };
+    if ok == 0 {
+        return Err(format!(
+            "GetDiskFreeSpaceExW failed: {}",
+            std::io::Error::last_os_error()
+        ));
+    }
+    Ok(DiskSpace {
+        total_bytes: total,
+        available_bytes: available,
+    })
+}
+
+// Disk space for the filesystem holding ~/.madola, so the frontend can warn
+// before a large export or import
+#[tauri::command]
+pub async fn get_disk_space() -> Result<DiskSpace, String> {
+    println!("[Rust] get_disk_space called");
+
+    let base = madola_base()?;
+    fs::create_dir_all(&base)
+        .map_err(|e| format!("Failed to create directory: {}", e))?;
+    disk_space_for(&base)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::paths::temp_dir;
+    use std::fs;
+
+    #[test]
+    fn oversized_files_are_rejected_before_reading() {
+        let dir = temp_dir("maxsize");
+        let path = dir.join("big.cpp");
+        fs::write(&path, vec![b'x'; 1025]).unwrap();
+
+        let err = check_file_size(&path, 1024).unwrap_err();
+        assert_eq!(err, "file too large (1025 bytes, limit 1024)");
+        assert!(check_file_size(&path, 1025).is_ok());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn line_ending_detection_and_style_round_trip() {
+        assert_eq!(detect_line_ending("a\nb\n"), "lf");
+        assert_eq!(detect_line_ending("a\r\nb\r\n"), "crlf");
+        assert_eq!(detect_line_ending("a\r\nb\n"), "mixed");
+        assert_eq!(detect_line_ending("no newline"), "lf");
+
+        assert_eq!(strip_bom("\u{feff}hi".to_string()), ("hi".to_string(), true));
+        assert_eq!(strip_bom("hi".to_string()), ("hi".to_string(), false));
+
+        assert_eq!(
+            apply_save_style("a\nb\n".to_string(), Some("crlf"), false),
+            "a\r\nb\r\n"
+        );
+        assert_eq!(
+            apply_save_style("a\r\nb\r\n".to_string(), Some("lf"), true),
+            "\u{feff}a\nb\n"
+        );
+    }
+
+    #[test]
+    fn open_rejects_directories_and_missing_paths() {
+        let dir = temp_dir("opendir");
+
+        let result = open_file_blocking(dir.to_string_lossy().to_string());
+        assert_eq!(
+            result.error.as_deref(),
+            Some("path is a directory, not a file")
+        );
+        let result = open_file_blocking(dir.join("missing.cpp").to_string_lossy().to_string());
+        assert_eq!(result.error.as_deref(), Some("path does not exist"));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn dropped_path_metadata_covers_files_dirs_and_ghosts() {
+        let dir = temp_dir("dropmeta");
+        fs::write(dir.join("code.CPP"), "int main() {}").unwrap();
+        fs::create_dir_all(dir.join("folder")).unwrap();
+
+        let described = describe_dropped_paths(&[
+            dir.join("code.CPP"),
+            dir.join("folder"),
+            dir.join("gone.cpp"),
+        ]);
+
+        assert!(described[0].exists && !described[0].is_dir);
+        assert_eq!(described[0].ext, "cpp");
+        assert_eq!(described[0].size, 13);
+
+        assert!(described[1].exists && described[1].is_dir);
+        assert_eq!(described[1].ext, "");
+
+        // Vanished paths stay in the list, flagged rather than dropped
+        assert!(!described[2].exists);
+        assert_eq!(described[2].path, dir.join("gone.cpp").to_string_lossy());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn directory_signatures_change_on_adds_edits_and_removals() {
+        let dir = temp_dir("dirsig");
+        fs::write(dir.join("a.cpp"), "one").unwrap();
+
+        let initial = dir_signature(&dir);
+
+        fs::write(dir.join("b.cpp"), "two").unwrap();
+        let added = dir_signature(&dir);
+        assert_ne!(initial, added);
+
+        // Growth changes total size even if the count stays put
+        fs::write(dir.join("b.cpp"), "two plus more").unwrap();
+        let grown = dir_signature(&dir);
+        assert_ne!(added, grown);
+
+        fs::remove_file(dir.join("b.cpp")).unwrap();
+        let removed = dir_signature(&dir);
+        assert_ne!(grown, removed);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn tail_reads_only_the_last_lines() {
+        let dir = temp_dir("tail");
+        let log = dir.join("madola.log");
+        let content: String = (0..500).map(|i| format!("line {}\n", i)).collect();
+        fs::write(&log, content).unwrap();
+
+        let tail = tail_lines(&log, 3).unwrap();
+        assert_eq!(tail, vec!["line 497", "line 498", "line 499"]);
+
+        // Asking for more lines than the file has returns the whole file
+        let all = tail_lines(&log, 10_000).unwrap();
+        assert_eq!(all.len(), 500);
+        assert_eq!(all[0], "line 0");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn readonly_line_windows_page_without_loading_everything() {
+        let dir = temp_dir("linewindow");
+        let path = dir.join("big.txt");
+        let content: String = (0..100).map(|i| format!("line {}\n", i)).collect();
+        fs::write(&path, &content).unwrap();
+        let len = content.len() as u64;
+
+        let starts = build_line_index(&path).unwrap();
+        assert_eq!(
+            read_line_window(&path, &starts, len, 0, 3).unwrap(),
+            vec!["line 0", "line 1", "line 2"]
+        );
+        assert_eq!(
+            read_line_window(&path, &starts, len, 97, 10).unwrap(),
+            vec!["line 97", "line 98", "line 99"]
+        );
+        // Entirely past the end is an empty window, not an error
+        assert!(read_line_window(&path, &starts, len, 100, 5).unwrap().is_empty());
+
+        // No trailing newline: the final line is still addressable
+        let tailless = dir.join("tailless.txt");
+        fs::write(&tailless, "a\nb\nend").unwrap();
+        let starts = build_line_index(&tailless).unwrap();
+        assert_eq!(
+            read_line_window(&tailless, &starts, 7, 2, 5).unwrap(),
+            vec!["end"]
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn line_index_cache_invalidates_on_mtime_change() {
+        let cache = LineIndexCache::default();
+        let old = SystemTime::UNIX_EPOCH;
+        let new = old + std::time::Duration::from_secs(60);
+
+        cache.store("file", old, Arc::new(vec![0, 10]));
+        assert!(cache.cached("file", old).is_some());
+        // A different mtime means the index is stale
+        assert!(cache.cached("file", new).is_none());
+        assert!(cache.cached("other", old).is_none());
+    }
+
+    #[test]
+    fn line_and_char_counts_handle_trailing_newlines_and_multibyte() {
+        assert_eq!(count_lines_chars(""), (0, 0));
+        assert_eq!(count_lines_chars("one\ntwo\n"), (2, 8));
+        // No trailing newline still counts the final line
+        assert_eq!(count_lines_chars("one\ntwo"), (2, 7));
+        // Multibyte characters count as chars, not bytes
+        assert_eq!(count_lines_chars("π = 3\n"), (1, 6));
+    }
+}
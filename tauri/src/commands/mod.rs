@@ -0,0 +1,463 @@
+//! App-level commands (settings, app info, window state) and the shared
+//! timeout/cancellation plumbing used by the feature-specific modules.
+
+pub mod cpp;
+pub mod files;
+pub mod wasm;
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+use tauri::Manager;
+
+use crate::paths::{madola_base, madola_base_source, madola_paths, window_prefs_path};
+use crate::types::{
+    load_settings, load_window_prefs, merge_window_prefs, save_settings, window_prefs_for, AppInfo,
+    HealthReport, Settings, WindowPrefs,
+};
+
+#[tauri::command]
+pub async fn get_settings() -> Settings {
+    load_settings()
+}
+
+#[tauri::command]
+pub async fn update_settings(window: tauri::Window, settings: Settings) -> Result<(), String> {
+    save_settings(&settings)?;
+    // Settings may steer the directory layout in the future; re-announce it
+    // so the frontend never works from a stale base
+    if let Ok(paths) = madola_paths() {
+        let _ = crate::events::emit_event(&window, crate::events::AppEvent::MadolaPaths(paths));
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_app_info() -> AppInfo {
+    AppInfo {
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        tauri_version: tauri::VERSION.to_string(),
+        os: std::env::consts::OS.to_string(),
+        arch: std::env::consts::ARCH.to_string(),
+        madola_home: madola_base()
+            .map(|base| base.to_string_lossy().to_string())
+            .unwrap_or_else(|e| e),
+        madola_home_overridden: std::env::var_os("MADOLA_HOME").is_some(),
+    }
+}
+
+// Probe a directory for writability by round-tripping a uniquely named
+// temp file; any step failing is the reason file operations would fail
+fn probe_writable(dir: &std::path::Path) -> Result<(), String> {
+    fs::create_dir_all(dir).map_err(|e| format!("cannot create {:?}: {}", dir, e))?;
+    let probe = dir.join(format!(".madola-health-{}", uuid::Uuid::new_v4()));
+    fs::write(&probe, b"probe").map_err(|e| format!("cannot write in {:?}: {}", dir, e))?;
+    fs::remove_file(&probe).map_err(|e| format!("cannot remove {:?}: {}", probe, e))
+}
+
+// Diagnostics panel: check every directory the file commands depend on in
+// one pass, collecting problems instead of failing on the first
+#[tauri::command]
+pub async fn health_check() -> HealthReport {
+    println!("[Rust] health_check called");
+
+    let mut errors = Vec::new();
+    let base = match madola_base() {
+        Ok(base) => Some(base),
+        Err(e) => {
+            errors.push(format!("cannot resolve base directory: {}", e));
+            None
+        }
+    };
+
+    let mut check = |dir: Option<std::path::PathBuf>| match dir {
+        Some(dir) => match probe_writable(&dir) {
+            Ok(()) => true,
+            Err(e) => {
+                errors.push(e);
+                false
+            }
+        },
+        None => false,
+    };
+
+    let base_writable = check(base.clone());
+    let gen_cpp_ok = check(base.as_ref().map(|b| b.join("gen_cpp")));
+    let trove_ok = check(base.map(|b| b.join("trove")));
+
+    HealthReport {
+        base_writable,
+        gen_cpp_ok,
+        trove_ok,
+        home_source: madola_base_source().to_string(),
+        errors,
+    }
+}
+
+#[tauri::command]
+pub async fn get_window_prefs(window: tauri::Window) -> WindowPrefs {
+    window_prefs_for(window.label())
+}
+
+#[tauri::command]
+pub async fn set_window_prefs(window: tauri::Window, prefs: WindowPrefs) -> Result<(), String> {
+    let mut all = load_window_prefs();
+    let merged = merge_window_prefs(all.get(window.label()), prefs);
+    all.insert(window.label().to_string(), merged);
+    save_window_prefs(&all)
+}
+
+fn save_window_prefs(all: &HashMap<String, WindowPrefs>) -> Result<(), String> {
+    let path = window_prefs_path()?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create directory: {}", e))?;
+    }
+    let content = serde_json::to_string_pretty(all)
+        .map_err(|e| format!("Failed to serialize window prefs: {}", e))?;
+    fs::write(&path, content)
+        .map_err(|e| format!("Failed to write window prefs: {}", e))
+}
+
+// Remember which file the window is editing so the next launch can restore
+// it; None clears the association (e.g. the editor closed the file)
+#[tauri::command]
+pub async fn set_active_file(window: tauri::Window, path: Option<String>) -> Result<(), String> {
+    let mut all = load_window_prefs();
+    all.entry(window.label().to_string()).or_default().active_file = path;
+    save_window_prefs(&all)
+}
+
+// Run blocking filesystem work off the async executor with a deadline, so a
+// stalled network mount fails the command instead of freezing the UI
+pub async fn with_timeout_secs<T, F>(secs: u64, f: F) -> Result<T, String>
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    let task = tauri::async_runtime::spawn_blocking(f);
+    match tokio::time::timeout(std::time::Duration::from_secs(secs), task).await {
+        Ok(Ok(value)) => Ok(value),
+        Ok(Err(e)) => Err(format!("Blocking task failed: {}", e)),
+        Err(_) => Err("operation timed out".to_string()),
+    }
+}
+
+pub async fn with_timeout<T, F>(f: F) -> Result<T, String>
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    with_timeout_secs(load_settings().command_timeout_secs, f).await
+}
+
+// Coalesces rapid title updates per window label: each request bumps a
+// generation counter and only the latest generation is applied after the
+// debounce window, so the last requested title always wins.
+#[derive(Default)]
+pub struct TitleDebouncer {
+    pending: Arc<Mutex<HashMap<String, (u64, String)>>>,
+}
+
+const TITLE_DEBOUNCE_MS: u64 = 100;
+
+impl TitleDebouncer {
+    fn submit(&self, label: &str, title: String) -> u64 {
+        let mut pending = self.pending.lock().unwrap();
+        let entry = pending.entry(label.to_string()).or_insert((0, String::new()));
+        entry.0 += 1;
+        entry.1 = title;
+        entry.0
+    }
+
+    // The title to apply, if this generation is still the latest for the label
+    fn title_if_current(&self, label: &str, generation: u64) -> Option<String> {
+        let pending = self.pending.lock().unwrap();
+        pending
+            .get(label)
+            .filter(|(current, _)| *current == generation)
+            .map(|(_, title)| title.clone())
+    }
+}
+
+// Window title management, debounced so per-keystroke updates don't flicker
+#[tauri::command]
+pub async fn set_title(
+    window: tauri::Window,
+    title: String,
+    debouncer: tauri::State<'_, TitleDebouncer>,
+) -> Result<(), String> {
+    let label = window.label().to_string();
+    let generation = debouncer.submit(&label, title);
+    let pending = TitleDebouncer {
+        pending: debouncer.pending.clone(),
+    };
+
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(std::time::Duration::from_millis(TITLE_DEBOUNCE_MS)).await;
+        if let Some(title) = pending.title_if_current(&label, generation) {
+            if let Err(e) = window.set_title(&title) {
+                println!("[Rust] ERROR setting title: {}", e);
+            }
+        }
+    });
+    Ok(())
+}
+
+// Windows with unsaved edits, by label. A dirty window's close request is
+// intercepted so the frontend can prompt before the edits are lost.
+#[derive(Default)]
+pub struct DirtyWindows(Mutex<HashSet<String>>);
+
+impl DirtyWindows {
+    pub fn is_dirty(&self, label: &str) -> bool {
+        self.0.lock().unwrap().contains(label)
+    }
+}
+
+// Track whether the window has unsaved edits; called by the frontend on
+// every edit/save transition
+#[tauri::command]
+pub async fn set_dirty(
+    window: tauri::Window,
+    dirty: bool,
+    state: tauri::State<'_, DirtyWindows>,
+) -> Result<(), String> {
+    let mut dirty_labels = state.0.lock().unwrap();
+    if dirty {
+        dirty_labels.insert(window.label().to_string());
+    } else {
+        dirty_labels.remove(window.label());
+    }
+    Ok(())
+}
+
+// Close a window regardless of its dirty flag, once the frontend has
+// resolved the confirm-close prompt
+#[tauri::command]
+pub async fn force_close(window: tauri::Window) -> Result<(), String> {
+    window
+        .state::<DirtyWindows>()
+        .0
+        .lock()
+        .unwrap()
+        .remove(window.label());
+    window
+        .close()
+        .map_err(|e| format!("Failed to close window: {}", e))
+}
+
+// Cancellation flags for long-running commands, keyed by a caller-chosen
+// operation id. Cancellation is cooperative, not preemptive: commands check
+// their flag between files, so the step already in flight still finishes.
+// Arc inside so a clone can travel into a background task.
+#[derive(Default, Clone)]
+pub struct CancelFlags(Arc<Mutex<HashMap<String, Arc<AtomicBool>>>>);
+
+pub const CANCELLED_MSG: &str = "operation cancelled";
+
+impl CancelFlags {
+    // The flag for an operation id; callers that pass no id get a private
+    // flag nothing can ever set
+    pub fn register(&self, op_id: &Option<String>) -> Arc<AtomicBool> {
+        match op_id {
+            Some(id) => self.0.lock().unwrap().entry(id.clone()).or_default().clone(),
+            None => Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    // Forget a finished operation so ids can be reused
+    pub fn finish(&self, op_id: &Option<String>) {
+        if let Some(id) = op_id {
+            self.0.lock().unwrap().remove(id);
+        }
+    }
+
+    // How many cancellable operations are still registered; shutdown waits
+    // for this to drain
+    pub fn in_flight(&self) -> usize {
+        self.0.lock().unwrap().len()
+    }
+}
+
+// How long an entry created by cancel_operation itself may linger before
+// it is reclaimed, when no operation ever picks it up
+const CANCEL_FLAG_TTL_MS: u64 = 5000;
+
+// Request cancellation of a long-running command that was started with a
+// matching op_id. Creating the flag here covers the race where the cancel
+// request arrives before the operation has registered itself.
+#[tauri::command]
+pub async fn cancel_operation(
+    id: String,
+    flags: tauri::State<'_, CancelFlags>,
+) -> Result<(), String> {
+    println!("[Rust] cancel_operation called: {}", id);
+    let (flag, existed) = {
+        let mut map = flags.0.lock().unwrap();
+        let existed = map.contains_key(&id);
+        let flag = map.entry(id.clone()).or_default().clone();
+        (flag, existed)
+    };
+    flag.store(true, Ordering::Relaxed);
+
+    if !existed {
+        // The target operation already finished, or never existed: expire
+        // the entry this call created so a stale id can't count towards
+        // in_flight() forever and stall the shutdown drain
+        let flags = flags.inner().clone();
+        tauri::async_runtime::spawn(async move {
+            tokio::time::sleep(std::time::Duration::from_millis(CANCEL_FLAG_TTL_MS)).await;
+            let mut map = flags.0.lock().unwrap();
+            if map.get(&id).is_some_and(|current| Arc::ptr_eq(current, &flag)) {
+                map.remove(&id);
+            }
+        });
+    }
+    Ok(())
+}
+
+// Upper bound on how long shutdown waits for in-flight operations (which
+// persist settings and prefs as they complete) before letting the process
+// exit anyway
+const SHUTDOWN_WAIT_MS: u64 = 2000;
+const SHUTDOWN_POLL_MS: u64 = 50;
+
+// Called from the RunEvent handler on exit: stop watchers so no more
+// events fire mid-teardown, give running saves a bounded window to finish,
+// then flush the persisted state files one last time. Idempotent, since
+// both ExitRequested and Exit can fire for one shutdown.
+pub fn flush_before_exit(app: &tauri::AppHandle) {
+    static FLUSHED: AtomicBool = AtomicBool::new(false);
+    if FLUSHED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    println!("[Rust] Shutting down: stopping watchers and flushing state");
+
+    app.state::<crate::commands::files::FileWatchers>().clear();
+    app.state::<crate::commands::files::DirWatch>().stop();
+    app.state::<crate::commands::files::LogStream>().stop();
+    app.state::<crate::commands::wasm::AutoCompiles>().clear();
+
+    // Bounded wait: long-running commands write settings/prefs as they
+    // finish, but shutdown must never hang on one
+    let flags = app.state::<CancelFlags>();
+    let deadline = std::time::Instant::now() + std::time::Duration::from_millis(SHUTDOWN_WAIT_MS);
+    while flags.in_flight() > 0 && std::time::Instant::now() < deadline {
+        std::thread::sleep(std::time::Duration::from_millis(SHUTDOWN_POLL_MS));
+    }
+    if flags.in_flight() > 0 {
+        println!("[Rust] WARNING: exiting with operations still in flight");
+    }
+
+    // Re-persist the current state so a torn write from a killed save is
+    // repaired before the process goes away
+    if let Err(e) = save_settings(&load_settings()) {
+        println!("[Rust] WARNING: could not flush settings: {}", e);
+    }
+    if let Err(e) = save_window_prefs(&load_window_prefs()) {
+        println!("[Rust] WARNING: could not flush window prefs: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn timeout_fires_on_slow_filesystem_work() {
+        // Simulate a stalled mount with a reader that sleeps far past the
+        // deadline; the command must return an error instead of hanging.
+        let result = tauri::async_runtime::block_on(with_timeout_secs(1, || {
+            std::thread::sleep(std::time::Duration::from_secs(10));
+            42
+        }));
+        assert_eq!(result, Err("operation timed out".to_string()));
+    }
+
+    #[test]
+    fn fast_work_completes_within_timeout() {
+        let result = tauri::async_runtime::block_on(with_timeout_secs(10, || 42));
+        assert_eq!(result, Ok(42));
+    }
+
+    #[test]
+    fn in_flight_count_tracks_registered_operations() {
+        let flags = CancelFlags::default();
+        assert_eq!(flags.in_flight(), 0);
+
+        let first = Some("op-1".to_string());
+        let second = Some("op-2".to_string());
+        flags.register(&first);
+        flags.register(&second);
+        // Anonymous operations get private flags and are never tracked
+        flags.register(&None);
+        assert_eq!(flags.in_flight(), 2);
+
+        flags.finish(&first);
+        assert_eq!(flags.in_flight(), 1);
+        flags.finish(&second);
+        assert_eq!(flags.in_flight(), 0);
+    }
+
+    #[test]
+    fn writability_probe_cleans_up_and_reports_failures() {
+        let dir = crate::paths::temp_dir("health");
+
+        assert!(probe_writable(&dir).is_ok());
+        // The probe file must not linger
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 0);
+
+        // A file squatting on the directory path is a reportable problem
+        let blocked = dir.join("blocked");
+        fs::write(&blocked, "file in the way").unwrap();
+        assert!(probe_writable(&blocked).is_err());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn rapid_title_updates_apply_only_the_latest() {
+        let debouncer = TitleDebouncer::default();
+
+        let mut last_generation = 0;
+        for i in 0..200 {
+            last_generation = debouncer.submit("main", format!("title {}", i));
+        }
+
+        // Every stale generation is dropped; only the newest one applies
+        assert_eq!(debouncer.title_if_current("main", last_generation - 1), None);
+        assert_eq!(
+            debouncer.title_if_current("main", last_generation),
+            Some("title 199".to_string())
+        );
+        // Labels are independent
+        assert_eq!(debouncer.title_if_current("other", last_generation), None);
+    }
+
+    #[test]
+    fn concurrent_blocking_work_does_not_serialize() {
+        let started = std::time::Instant::now();
+        tauri::async_runtime::block_on(async {
+            let tasks: Vec<_> = (0..32)
+                .map(|_| {
+                    tauri::async_runtime::spawn(with_timeout(|| {
+                        std::thread::sleep(std::time::Duration::from_millis(100));
+                    }))
+                })
+                .collect();
+            for task in tasks {
+                task.await.unwrap().unwrap();
+            }
+        });
+        // 32 sleeps of 100ms run serially would take 3.2s; on the blocking
+        // pool they should finish close to a single sleep
+        assert!(
+            started.elapsed() < std::time::Duration::from_secs(2),
+            "took {:?}",
+            started.elapsed()
+        );
+    }
+}
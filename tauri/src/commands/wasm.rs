@@ -0,0 +1,983 @@
+//! Commands for the ~/.madola/trove WASM modules: scanning, compiling,
+//! and manifest verification.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+use tauri::Manager;
+
+use crate::commands::files::hash_file_streaming;
+use crate::commands::{with_timeout, CancelFlags, CANCELLED_MSG};
+use crate::events::{emit_event, AppEvent};
+use crate::paths::{madola_base, validate_cpp_filename, validate_module_name};
+use crate::types::{
+    iso8601, load_settings, natural_cmp, sort_key_cmp, CompileOutput, DeleteResult, ModuleFile,
+    ModuleListResult, SortKey, VerifyResult, WasmHeader, WasmModule,
+};
+
+// Light heuristic, not security: wasm glue (e.g. from Emscripten) mentions
+// a sibling .wasm by name or a recognizable wasm marker. A .js file with
+// neither is probably an unrelated script that landed in the module folder.
+// Only the first chunk of the file is inspected.
+fn js_is_standalone(path: &Path, wasm_names: &[String]) -> bool {
+    use std::io::Read;
+
+    let mut head = Vec::new();
+    let read = fs::File::open(path)
+        .map(|f| f.take(256 * 1024))
+        .and_then(|mut f| f.read_to_end(&mut head));
+    if read.is_err() {
+        // Unreadable files already surface elsewhere; don't pile on
+        return false;
+    }
+    let text = String::from_utf8_lossy(&head);
+    let references_wasm = wasm_names.iter().any(|name| text.contains(name.as_str()))
+        || text.contains(".wasm")
+        || text.contains("WebAssembly");
+    !references_wasm
+}
+
+// File browser: Scan a trove directory for WASM modules
+fn scan_wasm_modules(trove_dir: &Path, sort: SortKey) -> ModuleListResult {
+    println!("[Rust] Looking in: {:?}", trove_dir);
+
+    // Same guard as scan_cpp_files: a file in the way of the directory path
+    // deserves a clear message, not a create_dir_all failure
+    if trove_dir.exists() && !trove_dir.is_dir() {
+        println!("[Rust] ERROR: trove exists but is not a directory");
+        return ModuleListResult {
+            success: false,
+            modules: vec![],
+            warnings: vec![],
+            error: Some("trove exists but is not a directory".to_string()),
+        };
+    }
+
+    // No exists() pre-check: it races against other windows scanning
+    // concurrently. create_dir_all is idempotent, and AlreadyExists from a
+    // lost race is success.
+    if let Err(e) = fs::create_dir_all(trove_dir) {
+        if e.kind() != std::io::ErrorKind::AlreadyExists {
+            println!("[Rust] ERROR creating directory: {}", e);
+            return ModuleListResult {
+                success: false,
+                modules: vec![],
+                warnings: vec![],
+                error: Some(format!("Failed to create directory: {}", e)),
+            };
+        }
+    }
+
+    let mut modules = Vec::new();
+    let mut warnings = Vec::new();
+
+    match fs::read_dir(trove_dir) {
+        Ok(entries) => {
+            for entry in entries.flatten() {
+                if let Ok(file_type) = entry.file_type() {
+                    if file_type.is_dir() {
+                        if let Ok(module_name) = entry.file_name().into_string() {
+                            println!("[Rust] Checking module directory: {}", module_name);
+                            let module_path = entry.path();
+                            let mut module_files = Vec::new();
+                            let mut latest: Option<std::time::SystemTime> = None;
+
+                            let module_entries = match fs::read_dir(&module_path) {
+                                Ok(entries) => entries,
+                                Err(e) => {
+                                    println!("[Rust] WARNING: cannot read module '{}': {}", module_name, e);
+                                    warnings.push(format!("module '{}': {}", module_name, e));
+                                    // Keep the module visible with an error
+                                    // status instead of dropping it
+                                    modules.push(WasmModule {
+                                        name: module_name,
+                                        files: vec![],
+                                        total_size: 0,
+                                        latest_modified: String::new(),
+                                        latest_modified_at: None,
+                                        status: "error".to_string(),
+                                    });
+                                    continue;
+                                }
+                            };
+                            for file_entry in module_entries.flatten() {
+                                if let Ok(file_name) = file_entry.file_name().into_string() {
+                                    if file_name.ends_with(".wasm") || file_name.ends_with(".js") {
+                                        if let Ok(metadata) = file_entry.metadata() {
+                                            if let Ok(modified) = metadata.modified() {
+                                                latest = std::cmp::max(latest, Some(modified));
+                                                let modified_str = format!("{:?}", modified);
+                                                let file_type = if file_name.ends_with(".wasm") {
+                                                    "wasm"
+                                                } else {
+                                                    "js"
+                                                };
+
+                                                println!("[Rust]   Found {} file: {} ({} bytes)", file_type, file_name, metadata.len());
+                                                module_files.push(ModuleFile {
+                                                    name: file_name,
+                                                    path: file_entry.path().to_string_lossy().to_string(),
+                                                    file_type: file_type.to_string(),
+                                                    size: metadata.len(),
+                                                    modified: modified_str,
+                                                    suspicious: false,
+                                                });
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+
+                            // Annotate .js files that don't look like glue
+                            // for any .wasm in this module
+                            let wasm_names: Vec<String> = module_files
+                                .iter()
+                                .filter(|f| f.file_type == "wasm")
+                                .map(|f| f.name.clone())
+                                .collect();
+                            for file in module_files.iter_mut().filter(|f| f.file_type == "js") {
+                                file.suspicious =
+                                    js_is_standalone(Path::new(&file.path), &wasm_names);
+                            }
+
+                            // An empty module folder is still a module: the
+                            // user just created it and the UI should say so
+                            // rather than have it vanish from the list
+                            let status = if module_files.is_empty() {
+                                "empty"
+                            } else {
+                                "ready"
+                            };
+                            println!(
+                                "[Rust] Added module '{}' with {} files ({})",
+                                module_name,
+                                module_files.len(),
+                                status
+                            );
+                            let total_size = module_files.iter().map(|f| f.size).sum();
+                            modules.push(WasmModule {
+                                name: module_name,
+                                files: module_files,
+                                total_size,
+                                latest_modified: latest.map(iso8601).unwrap_or_default(),
+                                latest_modified_at: latest,
+                                status: status.to_string(),
+                            });
+                        }
+                    }
+                }
+            }
+        }
+        Err(e) => {
+            println!("[Rust] ERROR reading directory: {}", e);
+            return ModuleListResult {
+                success: false,
+                modules: vec![],
+                warnings,
+                error: Some(format!("Failed to read directory: {}", e)),
+            };
+        }
+    }
+
+    modules.sort_by(|a, b| match sort {
+        SortKey::Size => b
+            .total_size
+            .cmp(&a.total_size)
+            .then_with(|| natural_cmp(&a.name, &b.name)),
+        SortKey::Modified => b
+            .latest_modified_at
+            .cmp(&a.latest_modified_at)
+            .then_with(|| natural_cmp(&a.name, &b.name)),
+        _ => sort_key_cmp(sort, &a.name, &b.name),
+    });
+    println!("[Rust] Returning {} WASM modules", modules.len());
+
+    ModuleListResult {
+        success: true,
+        modules,
+        warnings,
+        error: None,
+    }
+}
+
+// File browser: Get WASM modules from ~/.madola/trove
+#[tauri::command]
+pub async fn get_wasm_modules(sort: Option<SortKey>) -> ModuleListResult {
+    println!("[Rust] get_wasm_modules called");
+    let sort = sort.unwrap_or_default();
+
+    let trove_dir = match madola_base() {
+        Ok(base) => base.join("trove"),
+        Err(e) => {
+            println!("[Rust] ERROR: {}", e);
+            return ModuleListResult {
+                success: false,
+                modules: vec![],
+                warnings: vec![],
+                error: Some(e),
+            };
+        }
+    };
+
+    match with_timeout(move || scan_wasm_modules(&trove_dir, sort)).await {
+        Ok(result) => result,
+        Err(e) => {
+            println!("[Rust] ERROR scanning trove: {}", e);
+            ModuleListResult {
+                success: false,
+                modules: vec![],
+                warnings: vec![],
+                error: Some(e),
+            }
+        }
+    }
+}
+
+// Forward one of the compiler's output pipes to the frontend line by line
+fn stream_compiler_output<R: std::io::Read + Send + 'static>(
+    window: tauri::Window,
+    stream: &'static str,
+    reader: R,
+) -> std::thread::JoinHandle<()> {
+    std::thread::spawn(move || {
+        use std::io::BufRead;
+        for line in std::io::BufReader::new(reader).lines().map_while(Result::ok) {
+            let _ = emit_event(
+                &window,
+                AppEvent::CompileOutput(CompileOutput {
+                    stream: stream.to_string(),
+                    line,
+                }),
+            );
+        }
+    })
+}
+
+// Compile ~/.madola/gen_cpp/<filename> into a WASM module under
+// ~/.madola/trove/<module_name>/ using the compiler configured in settings.
+// Compiler stdout/stderr are streamed to the frontend as "compile-output"
+// events; on success the refreshed module list is returned.
+#[tauri::command]
+pub async fn compile_to_wasm(
+    window: tauri::Window,
+    filename: String,
+    module_name: String,
+    op_id: Option<String>,
+) -> ModuleListResult {
+    println!("[Rust] compile_to_wasm called: {} -> {}", filename, module_name);
+
+    let fail = |error: String| ModuleListResult {
+        success: false,
+        modules: vec![],
+        warnings: vec![],
+        error: Some(error),
+    };
+
+    if let Err(e) = validate_cpp_filename(&filename) {
+        return fail(e);
+    }
+    if let Err(e) = validate_module_name(&module_name) {
+        return fail(e);
+    }
+
+    let compiler = match load_settings().compiler_path {
+        Some(path) if !path.trim().is_empty() => path,
+        _ => {
+            return fail(
+                "No compiler configured: set compiler_path in settings first".to_string(),
+            )
+        }
+    };
+
+    let base = match madola_base() {
+        Ok(base) => base,
+        Err(e) => return fail(e),
+    };
+    let input = base.join("gen_cpp").join(&filename);
+    if !input.is_file() {
+        return fail(format!("File not found: {}", filename));
+    }
+    let module_dir = base.join("trove").join(&module_name);
+    if let Err(e) = fs::create_dir_all(&module_dir) {
+        return fail(format!("Failed to create directory: {}", e));
+    }
+    // Emscripten-style invocation: the .js output implies a sibling .wasm
+    let output = module_dir.join(format!("{}.js", module_name));
+
+    // Registered only once the early validations are done, so every exit
+    // from here on goes through finish
+    let cancel = window.state::<CancelFlags>().register(&op_id);
+
+    let worker_window = window.clone();
+    let worker_cancel = cancel.clone();
+    let outcome = tauri::async_runtime::spawn_blocking(move || {
+        use std::process::{Command, Stdio};
+        let window = worker_window;
+
+        let mut child = Command::new(&compiler)
+            .arg(&input)
+            .arg("-o")
+            .arg(&output)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("Failed to start compiler '{}': {}", compiler, e))?;
+
+        let stdout_thread = child
+            .stdout
+            .take()
+            .map(|out| stream_compiler_output(window.clone(), "stdout", out));
+        let stderr_thread = child
+            .stderr
+            .take()
+            .map(|err| stream_compiler_output(window, "stderr", err));
+
+        // An external process has no between-files checkpoint, so a
+        // cancelled compile kills the compiler instead
+        let status = loop {
+            if worker_cancel.load(Ordering::Relaxed) {
+                let _ = child.kill();
+                let _ = child.wait();
+                break None;
+            }
+            match child.try_wait() {
+                Ok(Some(status)) => break Some(status),
+                Ok(None) => std::thread::sleep(std::time::Duration::from_millis(100)),
+                Err(e) => return Err(format!("Failed to wait for compiler: {}", e)),
+            }
+        };
+        if let Some(thread) = stdout_thread {
+            let _ = thread.join();
+        }
+        if let Some(thread) = stderr_thread {
+            let _ = thread.join();
+        }
+        Ok::<_, String>(status)
+    })
+    .await;
+
+    window.state::<CancelFlags>().finish(&op_id);
+    match outcome {
+        Ok(Ok(None)) => fail(CANCELLED_MSG.to_string()),
+        Ok(Ok(Some(status))) if status.success() => {
+            scan_wasm_modules(&base.join("trove"), SortKey::default())
+        }
+        Ok(Ok(Some(status))) => fail(match status.code() {
+            Some(code) => format!("Compiler exited with code {}", code),
+            None => "Compiler was terminated by a signal".to_string(),
+        }),
+        Ok(Err(e)) => fail(e),
+        Err(e) => fail(format!("Blocking task failed: {}", e)),
+    }
+}
+
+// Shared body of delete_wasm_module, parameterized on the trove directory
+// so it can be exercised against a temp tree
+fn delete_wasm_module_in(trove_dir: &Path, module_name: &str, dry_run: bool) -> DeleteResult {
+    let fail = |error: String| DeleteResult {
+        success: false,
+        dry_run,
+        deleted: vec![],
+        error: Some(error),
+    };
+
+    if let Err(e) = validate_module_name(module_name) {
+        return fail(e);
+    }
+    let module_dir = trove_dir.join(module_name);
+    if !module_dir.is_dir() {
+        return fail(format!("Module not found: {}", module_name));
+    }
+
+    // Everything under the module goes, so list it all for the preview
+    let mut deleted = Vec::new();
+    let mut stack = vec![module_dir.clone()];
+    while let Some(dir) = stack.pop() {
+        if let Ok(entries) = fs::read_dir(&dir) {
+            for entry in entries.flatten() {
+                let path = entry.path();
+                if path.is_dir() {
+                    stack.push(path);
+                } else {
+                    deleted.push(path.to_string_lossy().to_string());
+                }
+            }
+        }
+    }
+    deleted.sort_by(|a, b| natural_cmp(a, b));
+
+    if !dry_run {
+        if let Err(e) = fs::remove_dir_all(&module_dir) {
+            return fail(format!("Failed to delete module: {}", e));
+        }
+    }
+    DeleteResult {
+        success: true,
+        dry_run,
+        deleted,
+        error: None,
+    }
+}
+
+// Delete a whole trove module. With dry_run the files that would go are
+// returned without touching the filesystem.
+#[tauri::command]
+pub async fn delete_wasm_module(module_name: String, dry_run: Option<bool>) -> DeleteResult {
+    println!("[Rust] delete_wasm_module called: {}", module_name);
+    let dry_run = dry_run.unwrap_or(false);
+
+    let trove_dir = match madola_base() {
+        Ok(base) => base.join("trove"),
+        Err(e) => {
+            return DeleteResult {
+                success: false,
+                dry_run,
+                deleted: vec![],
+                error: Some(e),
+            }
+        }
+    };
+    match with_timeout(move || delete_wasm_module_in(&trove_dir, &module_name, dry_run)).await {
+        Ok(result) => result,
+        Err(e) => DeleteResult {
+            success: false,
+            dry_run,
+            deleted: vec![],
+            error: Some(e),
+        },
+    }
+}
+
+// How much of a .wasm the header peek will read; enough for the section
+// layout of any reasonably sized module without pulling megabytes of code
+const WASM_PEEK_BYTES: u64 = 256 * 1024;
+
+// Parse just enough of a wasm binary to report its version and top-level
+// section names. A module longer than the peek window parses as far as the
+// window reaches and keeps what it saw.
+fn peek_wasm_header_at(path: &Path) -> Result<WasmHeader, String> {
+    use std::io::Read;
+
+    let mut bytes = Vec::new();
+    fs::File::open(path)
+        .map_err(|e| format!("Failed to open file: {}", e))?
+        .take(WASM_PEEK_BYTES)
+        .read_to_end(&mut bytes)
+        .map_err(|e| format!("Failed to read file: {}", e))?;
+
+    // Wrong magic means this isn't wasm at all; say so via the flag instead
+    // of a parse error the UI would have to interpret
+    if bytes.len() < 8 || &bytes[0..4] != b"\0asm" {
+        return Ok(WasmHeader {
+            magic_ok: false,
+            version: 0,
+            section_names: vec![],
+        });
+    }
+
+    let mut version = 0u32;
+    let mut section_names = Vec::new();
+    for payload in wasmparser::Parser::new(0).parse_all(&bytes) {
+        let payload = match payload {
+            Ok(payload) => payload,
+            // Likely the peek window cut a section short; report what we saw
+            Err(_) => break,
+        };
+        use wasmparser::Payload;
+        let name = match &payload {
+            Payload::Version { num, .. } => {
+                version = u32::from(*num);
+                continue;
+            }
+            Payload::CustomSection(reader) => reader.name().to_string(),
+            Payload::TypeSection(_) => "type".to_string(),
+            Payload::ImportSection(_) => "import".to_string(),
+            Payload::FunctionSection(_) => "function".to_string(),
+            Payload::TableSection(_) => "table".to_string(),
+            Payload::MemorySection(_) => "memory".to_string(),
+            Payload::TagSection(_) => "tag".to_string(),
+            Payload::GlobalSection(_) => "global".to_string(),
+            Payload::ExportSection(_) => "export".to_string(),
+            Payload::StartSection { .. } => "start".to_string(),
+            Payload::ElementSection(_) => "element".to_string(),
+            Payload::DataCountSection { .. } => "datacount".to_string(),
+            Payload::DataSection(_) => "data".to_string(),
+            Payload::CodeSectionStart { .. } => "code".to_string(),
+            Payload::End(_) => break,
+            _ => continue,
+        };
+        section_names.push(name);
+    }
+
+    Ok(WasmHeader {
+        magic_ok: true,
+        version,
+        section_names,
+    })
+}
+
+// Module-details panel: header/version/section summary of one trove file
+#[tauri::command]
+pub async fn peek_wasm_header(module: String, file: String) -> Result<WasmHeader, String> {
+    println!("[Rust] peek_wasm_header called: {}/{}", module, file);
+    validate_module_name(&module)?;
+    if file.is_empty() || file.contains('/') || file.contains('\\') || file.contains("..") {
+        return Err("File must be a single path component".to_string());
+    }
+
+    let path = madola_base()?.join("trove").join(&module).join(&file);
+    if !path.is_file() {
+        return Err(format!("File not found: {}/{}", module, file));
+    }
+    with_timeout(move || peek_wasm_header_at(&path)).await?
+}
+
+// Launch the platform file manager on a directory. Fire-and-forget: the
+// child is not waited on.
+fn open_in_file_manager(dir: &Path) -> Result<(), String> {
+    #[cfg(target_os = "macos")]
+    let program = "open";
+    #[cfg(target_os = "windows")]
+    let program = "explorer";
+    #[cfg(all(unix, not(target_os = "macos")))]
+    let program = "xdg-open";
+
+    std::process::Command::new(program)
+        .arg(dir)
+        .spawn()
+        .map(|_| ())
+        .map_err(|e| format!("Failed to open file manager: {}", e))
+}
+
+// "Show me where this actually is" for a module in the browser
+#[tauri::command]
+pub async fn open_module_folder(module_name: String) -> Result<(), String> {
+    println!("[Rust] open_module_folder called: {}", module_name);
+    validate_module_name(&module_name)?;
+
+    let module_dir = madola_base()?.join("trove").join(&module_name);
+    // Opening the parent when the module is gone would be misleading
+    if !module_dir.is_dir() {
+        return Err(format!("Module not found: {}", module_name));
+    }
+    open_in_file_manager(&module_dir)
+}
+
+// Active auto-compile watchers keyed by source filename. Dropping the
+// watcher stops event delivery, as with FileWatchers.
+#[derive(Default)]
+pub struct AutoCompiles(Mutex<HashMap<String, notify::RecommendedWatcher>>);
+
+impl AutoCompiles {
+    // Drop every watch; used by shutdown
+    pub fn clear(&self) {
+        self.0.lock().unwrap().clear();
+    }
+}
+
+// Quiet period after a change before the recompile starts, so a burst of
+// writes triggers one compile
+const AUTO_COMPILE_DEBOUNCE_MS: u64 = 300;
+
+// Per-watch trigger bookkeeping: a generation counter coalesces bursts of
+// change events and a compiling flag keeps two compiles of the same file
+// from overlapping
+#[derive(Default)]
+struct AutoCompileTrigger {
+    generation: Mutex<u64>,
+    compiling: AtomicBool,
+}
+
+impl AutoCompileTrigger {
+    fn bump(&self) -> u64 {
+        let mut current = self.generation.lock().unwrap();
+        *current += 1;
+        *current
+    }
+
+    fn current(&self) -> u64 {
+        *self.generation.lock().unwrap()
+    }
+
+    // Whether the debounced task for `generation` should run the compile:
+    // stale generations bail (a newer change supersedes them), and so do
+    // triggers racing an already-running compile, which will loop and pick
+    // the newer generation up itself
+    fn claim(&self, generation: u64) -> bool {
+        if self.current() != generation {
+            return false;
+        }
+        !self.compiling.swap(true, Ordering::SeqCst)
+    }
+
+    fn release(&self) {
+        self.compiling.store(false, Ordering::SeqCst);
+    }
+}
+
+// Debounce one change event and, if it is still the latest, compile until
+// the generation stops moving, emitting each outcome as "auto-compile-result"
+fn schedule_auto_compile(
+    window: tauri::Window,
+    filename: String,
+    module_name: String,
+    trigger: Arc<AutoCompileTrigger>,
+) {
+    let generation = trigger.bump();
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(std::time::Duration::from_millis(AUTO_COMPILE_DEBOUNCE_MS)).await;
+        if !trigger.claim(generation) {
+            return;
+        }
+        loop {
+            let before = trigger.current();
+            let result =
+                compile_to_wasm(window.clone(), filename.clone(), module_name.clone(), None).await;
+            let _ = emit_event(&window, AppEvent::AutoCompileResult(result));
+            // Changes that arrived mid-compile get exactly one more pass
+            if trigger.current() == before {
+                break;
+            }
+        }
+        trigger.release();
+    });
+}
+
+// Watch mode: recompile a gen_cpp file into its module whenever it changes
+// on disk, debounced so editor save bursts compile once
+#[tauri::command]
+pub async fn start_auto_compile(
+    window: tauri::Window,
+    filename: String,
+    module_name: String,
+    watches: tauri::State<'_, AutoCompiles>,
+) -> Result<(), String> {
+    println!(
+        "[Rust] start_auto_compile called: {} -> {}",
+        filename, module_name
+    );
+    validate_cpp_filename(&filename)?;
+    validate_module_name(&module_name)?;
+
+    let source = madola_base()?.join("gen_cpp").join(&filename);
+    if !source.is_file() {
+        return Err(format!("File not found: {}", filename));
+    }
+
+    let mut map = watches.0.lock().unwrap();
+    if map.contains_key(&filename) {
+        // Re-registering the same file must not stack duplicate watchers
+        return Ok(());
+    }
+
+    use notify::Watcher;
+    let trigger = Arc::new(AutoCompileTrigger::default());
+    let watch_filename = filename.clone();
+    let mut watcher =
+        notify::recommended_watcher(move |result: Result<notify::Event, notify::Error>| {
+            if let Ok(event) = result {
+                if matches!(
+                    event.kind,
+                    notify::EventKind::Modify(_) | notify::EventKind::Create(_)
+                ) {
+                    schedule_auto_compile(
+                        window.clone(),
+                        watch_filename.clone(),
+                        module_name.clone(),
+                        trigger.clone(),
+                    );
+                }
+            }
+        })
+        .map_err(|e| format!("Failed to create watcher: {}", e))?;
+    watcher
+        .watch(&source, notify::RecursiveMode::NonRecursive)
+        .map_err(|e| format!("Failed to watch file: {}", e))?;
+    map.insert(filename, watcher);
+    Ok(())
+}
+
+// Stop a watch previously registered with start_auto_compile
+#[tauri::command]
+pub async fn stop_auto_compile(
+    filename: String,
+    watches: tauri::State<'_, AutoCompiles>,
+) -> Result<(), String> {
+    println!("[Rust] stop_auto_compile called: {}", filename);
+
+    use notify::Watcher;
+    let mut map = watches.0.lock().unwrap();
+    if let Some(mut watcher) = map.remove(&filename) {
+        if let Ok(base) = madola_base() {
+            let _ = watcher.unwatch(&base.join("gen_cpp").join(&filename));
+        }
+    }
+    Ok(())
+}
+
+// Synchronous body of verify_module, parameterized on the directory so it
+// can be exercised against a temp tree
+fn verify_module_dir(module_dir: &Path) -> Result<VerifyResult, String> {
+    if !module_dir.is_dir() {
+        return Err("module does not exist".to_string());
+    }
+    let manifest_path = module_dir.join("manifest.json");
+    if !manifest_path.exists() {
+        // Distinct from a failed verification: with no manifest there is
+        // nothing to verify against
+        return Err("module has no manifest.json".to_string());
+    }
+    let manifest: HashMap<String, String> = fs::read_to_string(&manifest_path)
+        .map_err(|e| format!("Failed to read manifest: {}", e))
+        .and_then(|content| {
+            serde_json::from_str(&content).map_err(|e| format!("Failed to parse manifest: {}", e))
+        })?;
+
+    let mut missing = Vec::new();
+    let mut mismatched = Vec::new();
+    for (name, expected) in &manifest {
+        let path = module_dir.join(name);
+        if !path.is_file() {
+            missing.push(name.clone());
+            continue;
+        }
+        match hash_file_streaming(&path) {
+            Ok(actual) if actual.eq_ignore_ascii_case(expected) => {}
+            _ => mismatched.push(name.clone()),
+        }
+    }
+
+    let mut extra = Vec::new();
+    if let Ok(entries) = fs::read_dir(module_dir) {
+        for entry in entries.flatten() {
+            if let Ok(name) = entry.file_name().into_string() {
+                if name != "manifest.json" && !manifest.contains_key(&name) {
+                    extra.push(name);
+                }
+            }
+        }
+    }
+
+    missing.sort();
+    mismatched.sort();
+    extra.sort();
+    let ok = missing.is_empty() && mismatched.is_empty() && extra.is_empty();
+    Ok(VerifyResult {
+        ok,
+        missing,
+        mismatched,
+        extra,
+    })
+}
+
+// Verify a trove module against its manifest.json, a map of file name to
+// expected SHA-256, so users can confirm a module wasn't tampered with or
+// partially copied
+#[tauri::command]
+pub async fn verify_module(module_name: String) -> Result<VerifyResult, String> {
+    println!("[Rust] verify_module called: {}", module_name);
+    validate_module_name(&module_name)?;
+    let module_dir = madola_base()?.join("trove").join(&module_name);
+    with_timeout(move || verify_module_dir(&module_dir)).await?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::files::hash_file_streaming;
+    use crate::paths::temp_dir;
+    use std::fs;
+
+    #[test]
+    fn scan_wasm_modules_reports_file_in_the_way() {
+        let dir = temp_dir("trovefile");
+        let bogus = dir.join("trove");
+        fs::write(&bogus, "not a directory").unwrap();
+
+        let result = scan_wasm_modules(&bogus, SortKey::Natural);
+        assert!(!result.success);
+        assert_eq!(
+            result.error.as_deref(),
+            Some("trove exists but is not a directory")
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn concurrent_scans_create_the_trove_without_spurious_failures() {
+        let dir = temp_dir("troverace");
+        let trove = dir.join("trove");
+
+        let handles: Vec<_> = (0..16)
+            .map(|_| {
+                let trove = trove.clone();
+                std::thread::spawn(move || scan_wasm_modules(&trove, SortKey::Natural))
+            })
+            .collect();
+        for handle in handles {
+            let result = handle.join().unwrap();
+            assert!(result.success, "spurious failure: {:?}", result.error);
+        }
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn standalone_js_without_wasm_reference_is_flagged() {
+        let dir = temp_dir("suspicious");
+        let module = dir.join("mymod");
+        fs::create_dir_all(&module).unwrap();
+        fs::write(module.join("mymod.wasm"), [0x00, 0x61, 0x73, 0x6d]).unwrap();
+        fs::write(
+            module.join("mymod.js"),
+            "var wasmBinaryFile = 'mymod.wasm';",
+        )
+        .unwrap();
+        fs::write(module.join("rogue.js"), "console.log('hello');").unwrap();
+
+        let result = scan_wasm_modules(&dir, SortKey::Natural);
+        assert!(result.success);
+        let files = &result.modules[0].files;
+        let flag = |name: &str| files.iter().find(|f| f.name == name).unwrap().suspicious;
+        assert!(!flag("mymod.wasm"));
+        assert!(!flag("mymod.js"));
+        assert!(flag("rogue.js"));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn module_aggregates_track_size_and_newest_mtime() {
+        let dir = temp_dir("aggregates");
+        let big = dir.join("big");
+        let small = dir.join("small");
+        fs::create_dir_all(&big).unwrap();
+        fs::create_dir_all(&small).unwrap();
+        fs::write(big.join("big.wasm"), vec![0u8; 1000]).unwrap();
+        fs::write(big.join("big.js"), vec![b'/'; 200]).unwrap();
+        fs::write(small.join("small.wasm"), vec![0u8; 10]).unwrap();
+        fs::create_dir_all(dir.join("fresh")).unwrap();
+
+        let result = scan_wasm_modules(&dir, SortKey::Size);
+        assert!(result.success);
+        // Largest first under the size key
+        assert_eq!(result.modules[0].name, "big");
+        assert_eq!(result.modules[0].total_size, 1200);
+        assert_eq!(result.modules[1].total_size, 10);
+        assert_eq!(result.modules[0].status, "ready");
+        // A just-created folder with no artifacts still shows up
+        let fresh = result.modules.iter().find(|m| m.name == "fresh").unwrap();
+        assert_eq!(fresh.status, "empty");
+        assert!(fresh.files.is_empty());
+        // RFC 3339, e.g. "2026-08-31T12:00:00+00:00"
+        assert!(result.modules[0].latest_modified.contains('T'));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn module_deletion_previews_in_dry_run_and_deletes_for_real() {
+        let dir = temp_dir("moddelete");
+        let module = dir.join("mymod");
+        fs::create_dir_all(module.join("nested")).unwrap();
+        fs::write(module.join("mymod.wasm"), b"wasm").unwrap();
+        fs::write(module.join("nested").join("extra.js"), b"js").unwrap();
+
+        let preview = delete_wasm_module_in(&dir, "mymod", true);
+        assert!(preview.success);
+        assert!(preview.dry_run);
+        assert_eq!(preview.deleted.len(), 2);
+        assert!(module.is_dir());
+
+        let real = delete_wasm_module_in(&dir, "mymod", false);
+        assert!(real.success);
+        assert!(!real.dry_run);
+        assert!(!module.exists());
+
+        // Gone now, so another delete is an error, not a silent no-op
+        let missing = delete_wasm_module_in(&dir, "mymod", false);
+        assert!(!missing.success);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn auto_compile_triggers_coalesce_and_never_overlap() {
+        let trigger = AutoCompileTrigger::default();
+        let first = trigger.bump();
+        let second = trigger.bump();
+
+        // The superseded generation bails; the latest one claims the compile
+        assert!(!trigger.claim(first));
+        assert!(trigger.claim(second));
+
+        // A trigger racing the running compile bails too; the runner loops
+        let third = trigger.bump();
+        assert!(!trigger.claim(third));
+
+        trigger.release();
+        assert!(trigger.claim(third));
+    }
+
+    #[test]
+    fn wasm_header_peek_reports_sections_and_flags_non_wasm() {
+        let dir = temp_dir("wasmpeek");
+        fs::create_dir_all(&dir).unwrap();
+
+        // magic + version 1, a "name" custom section, and an empty type
+        // section, hand-assembled
+        let mut wasm: Vec<u8> = vec![0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00];
+        wasm.extend([0x00, 0x05, 0x04]); // custom section, size 5, name len 4
+        wasm.extend(b"name");
+        wasm.extend([0x01, 0x01, 0x00]); // type section, size 1, zero types
+        fs::write(dir.join("mod.wasm"), &wasm).unwrap();
+
+        let header = peek_wasm_header_at(&dir.join("mod.wasm")).unwrap();
+        assert!(header.magic_ok);
+        assert_eq!(header.version, 1);
+        assert_eq!(header.section_names, vec!["name", "type"]);
+
+        fs::write(dir.join("not.wasm"), b"console.log('js');").unwrap();
+        let bogus = peek_wasm_header_at(&dir.join("not.wasm")).unwrap();
+        assert!(!bogus.magic_ok);
+        assert!(bogus.section_names.is_empty());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn verify_module_reports_missing_mismatched_and_extra() {
+        let dir = temp_dir("verify");
+        let module = dir.join("mod");
+        fs::create_dir_all(&module).unwrap();
+        fs::write(module.join("good.wasm"), b"payload").unwrap();
+        fs::write(module.join("bad.js"), b"changed").unwrap();
+        fs::write(module.join("stray.js"), b"stray").unwrap();
+
+        // No manifest yet: an error, not a failed verification
+        match verify_module_dir(&module) {
+            Err(e) => assert_eq!(e, "module has no manifest.json"),
+            Ok(_) => panic!("expected missing-manifest error"),
+        }
+
+        let good_hash = hash_file_streaming(&module.join("good.wasm")).unwrap();
+        let manifest = serde_json::json!({
+            "good.wasm": good_hash,
+            "bad.js": "0000000000000000000000000000000000000000000000000000000000000000",
+            "gone.wasm": "1111111111111111111111111111111111111111111111111111111111111111",
+        });
+        fs::write(module.join("manifest.json"), manifest.to_string()).unwrap();
+
+        let result = verify_module_dir(&module).unwrap();
+        assert!(!result.ok);
+        assert_eq!(result.missing, vec!["gone.wasm"]);
+        assert_eq!(result.mismatched, vec!["bad.js"]);
+        assert_eq!(result.extra, vec!["stray.js"]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
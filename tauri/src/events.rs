@@ -0,0 +1,107 @@
+//! Typed events and the single helper that emits them. Every payload
+//! leaves the backend wrapped in an envelope stamped with
+//! `schema_version`, so the frontend can validate before trusting the
+//! shape.
+
+use serde::Serialize;
+
+use crate::types::{
+    CompileOutput, DroppedPath, FileChunk, FileContentResult, ImportedFile, MadolaPaths,
+    ModuleListResult, ProgressPayload, WindowPrefs,
+};
+
+// Bump whenever any payload shape changes incompatibly
+pub const EVENT_SCHEMA_VERSION: u32 = 1;
+
+// Every event the backend emits, with its typed payload. New events get a
+// variant here instead of a stringly-typed emit at the call site, which
+// keeps the whole event surface reviewable in one place.
+#[derive(Serialize)]
+#[serde(untagged)]
+pub enum AppEvent {
+    ApplyWindowPrefs(WindowPrefs),
+    MadolaPaths(MadolaPaths),
+    RestoreFile(FileContentResult),
+    FilesImported(Vec<ImportedFile>),
+    FileDropped(Vec<DroppedPath>),
+    ConfirmClose,
+    FileExternallyModified(String),
+    GenCppChanged,
+    TroveChanged,
+    LogLine(String),
+    FileChunk(FileChunk),
+    CompileOutput(CompileOutput),
+    AutoCompileResult(ModuleListResult),
+    ExportProgress(ProgressPayload),
+    ImportProgress(ProgressPayload),
+    ExportDone(usize),
+    ImportDone(usize),
+}
+
+impl AppEvent {
+    pub fn name(&self) -> &'static str {
+        match self {
+            AppEvent::ApplyWindowPrefs(_) => "apply-window-prefs",
+            AppEvent::MadolaPaths(_) => "madola-paths",
+            AppEvent::RestoreFile(_) => "restore-file",
+            AppEvent::FilesImported(_) => "files-imported",
+            AppEvent::FileDropped(_) => "file-dropped",
+            AppEvent::ConfirmClose => "confirm-close",
+            AppEvent::FileExternallyModified(_) => "file-externally-modified",
+            AppEvent::GenCppChanged => "gen-cpp-changed",
+            AppEvent::TroveChanged => "trove-changed",
+            AppEvent::LogLine(_) => "log-line",
+            AppEvent::FileChunk(_) => "file-chunk",
+            AppEvent::CompileOutput(_) => "compile-output",
+            AppEvent::AutoCompileResult(_) => "auto-compile-result",
+            AppEvent::ExportProgress(_) => "export-progress",
+            AppEvent::ImportProgress(_) => "import-progress",
+            AppEvent::ExportDone(_) => "export-done",
+            AppEvent::ImportDone(_) => "import-done",
+        }
+    }
+}
+
+// What actually goes over the wire: `payload` is the variant's value
+// serialized bare (no enum tag), next to the schema stamp
+#[derive(Serialize, Clone)]
+struct EventEnvelope {
+    schema_version: u32,
+    payload: serde_json::Value,
+}
+
+// The one way events leave the backend
+pub fn emit_event(window: &tauri::Window, event: AppEvent) -> Result<(), String> {
+    let name = event.name();
+    let payload = serde_json::to_value(&event)
+        .map_err(|e| format!("Failed to serialize {} event: {}", name, e))?;
+    window
+        .emit(
+            name,
+            EventEnvelope {
+                schema_version: EVENT_SCHEMA_VERSION,
+                payload,
+            },
+        )
+        .map_err(|e| format!("Failed to emit {} event: {}", name, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn events_serialize_as_bare_payloads_with_stable_names() {
+        let event = AppEvent::LogLine("hello".to_string());
+        assert_eq!(event.name(), "log-line");
+        // Untagged: the payload crosses the wire without an enum wrapper
+        assert_eq!(
+            serde_json::to_value(&event).unwrap(),
+            serde_json::json!("hello")
+        );
+
+        let unit = AppEvent::GenCppChanged;
+        assert_eq!(unit.name(), "gen-cpp-changed");
+        assert_eq!(serde_json::to_value(&unit).unwrap(), serde_json::Value::Null);
+    }
+}
@@ -1,344 +1,208 @@
 // Prevents additional console window on Windows in release mode
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
-use tauri::{Manager, WindowEvent};
-use std::fs;
-use std::path::PathBuf;
-use serde::{Serialize, Deserialize};
+mod commands;
+mod events;
+mod paths;
+mod types;
+
+use tauri::{
+    CustomMenuItem, Manager, SystemTray, SystemTrayEvent, SystemTrayMenu, SystemTrayMenuItem,
+    WindowEvent,
+};
+
+use commands::cpp::import_dropped_files;
+use commands::DirtyWindows;
+use events::{emit_event, AppEvent};
+use paths::madola_base;
+use types::{load_settings, window_prefs_for};
 
-#[derive(Serialize, Deserialize)]
-struct FileInfo {
-    name: String,
-    size: u64,
-    modified: String,
-}
-
-#[derive(Serialize, Deserialize)]
-struct FileListResult {
-    success: bool,
-    files: Vec<FileInfo>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    error: Option<String>,
-}
-
-#[derive(Serialize, Deserialize)]
-struct ModuleFile {
-    name: String,
-    #[serde(rename = "type")]
-    file_type: String,
-    size: u64,
-    modified: String,
-}
-
-#[derive(Serialize, Deserialize)]
-struct WasmModule {
-    name: String,
-    files: Vec<ModuleFile>,
-}
-
-#[derive(Serialize, Deserialize)]
-struct ModuleListResult {
-    success: bool,
-    modules: Vec<WasmModule>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    error: Option<String>,
-}
-
-#[derive(Serialize, Deserialize)]
-struct FileContentResult {
-    success: bool,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    content: Option<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    filename: Option<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    error: Option<String>,
-}
-
-// File operations
-#[tauri::command]
-async fn open_file(path: String) -> Result<String, String> {
-    fs::read_to_string(&path)
-        .map_err(|e| format!("Failed to read file: {}", e))
-}
-
-#[tauri::command]
-async fn save_file(path: String, content: String) -> Result<(), String> {
-    fs::write(&path, content)
-        .map_err(|e| format!("Failed to write file: {}", e))
-}
-
-#[tauri::command]
-async fn get_file_name(path: String) -> Result<String, String> {
-    let path_buf = PathBuf::from(path);
-    path_buf
-        .file_name()
-        .and_then(|n| n.to_str())
-        .map(|s| s.to_string())
-        .ok_or_else(|| "Failed to get file name".to_string())
-}
-
-// Window title management
-#[tauri::command]
-async fn set_title(window: tauri::Window, title: String) -> Result<(), String> {
-    window.set_title(&title)
-        .map_err(|e| format!("Failed to set title: {}", e))
-}
-
-// File browser: Get C++ files from ~/.madola/gen_cpp
-#[tauri::command]
-async fn get_cpp_files() -> FileListResult {
-    println!("[Rust] get_cpp_files called");
-    
-    let home_dir = match dirs::home_dir() {
-        Some(dir) => {
-            println!("[Rust] Home dir: {:?}", dir);
-            dir
-        },
-        None => {
-            println!("[Rust] ERROR: Could not determine home directory");
-            return FileListResult {
-                success: false,
-                files: vec![],
-                error: Some("Could not determine home directory".to_string()),
+fn main() {
+    tauri::Builder::default()
+        .invoke_handler(tauri::generate_handler![
+            commands::files::open_file,
+            commands::files::open_file_readonly_lines,
+            commands::files::stream_file,
+            commands::files::save_file,
+            commands::files::get_file_name,
+            commands::set_title,
+            commands::set_dirty,
+            commands::get_window_prefs,
+            commands::set_window_prefs,
+            commands::set_active_file,
+            commands::force_close,
+            commands::cpp::get_cpp_files,
+            commands::cpp::get_projects,
+            commands::cpp::assign_file_to_project,
+            commands::cpp::list_external_cpp,
+            commands::cpp::read_external_file,
+            commands::wasm::get_wasm_modules,
+            commands::wasm::compile_to_wasm,
+            commands::wasm::start_auto_compile,
+            commands::wasm::stop_auto_compile,
+            commands::wasm::verify_module,
+            commands::wasm::open_module_folder,
+            commands::wasm::delete_wasm_module,
+            commands::wasm::peek_wasm_header,
+            commands::cpp::get_cpp_file_content,
+            commands::cpp::export_gen_cpp_zip,
+            commands::cpp::import_gen_cpp_zip,
+            commands::cpp::save_cpp_file,
+            commands::cpp::delete_cpp_file,
+            commands::cpp::rename_cpp_file,
+            commands::cpp::undo_last_operation,
+            commands::cpp::move_cpp_to_module,
+            commands::files::get_disk_space,
+            commands::get_settings,
+            commands::update_settings,
+            commands::get_app_info,
+            commands::health_check,
+            commands::cpp::hash_cpp_file,
+            commands::cpp::create_cpp_file,
+            commands::cpp::list_cpp_templates,
+            commands::cpp::diff_cpp_content,
+            commands::cpp::cpp_outline,
+            commands::cpp::get_cpp_files_content,
+            commands::files::pick_file,
+            commands::files::pick_save_path,
+            commands::files::watch_file,
+            commands::files::unwatch_file,
+            commands::files::start_dir_watch,
+            commands::files::stop_dir_watch,
+            commands::cancel_operation,
+            commands::files::tail_log,
+            commands::files::start_log_stream,
+            commands::files::stop_log_stream,
+            commands::cpp::gen_cpp_extension_stats,
+            commands::cpp::request_clear_token,
+            commands::cpp::clear_gen_cpp
+        ])
+        .manage(commands::cpp::FileLocks::default())
+        .manage(commands::files::FileWatchers::default())
+        .manage(commands::files::DirWatch::default())
+        .manage(commands::cpp::ClearTokens::default())
+        .manage(commands::CancelFlags::default())
+        .manage(commands::DirtyWindows::default())
+        .manage(commands::files::LogStream::default())
+        .manage(commands::files::LineIndexCache::default())
+        .manage(commands::cpp::TrashHistory::default())
+        .manage(commands::wasm::AutoCompiles::default())
+        .manage(commands::TitleDebouncer::default())
+        .system_tray(
+            SystemTray::new().with_menu(
+                SystemTrayMenu::new()
+                    .add_item(CustomMenuItem::new("show".to_string(), "Show"))
+                    .add_native_item(SystemTrayMenuItem::Separator)
+                    .add_item(CustomMenuItem::new("quit".to_string(), "Quit")),
+            ),
+        )
+        .on_system_tray_event(|app, event| {
+            let show_main = |app: &tauri::AppHandle| {
+                if let Some(window) = app.get_window("main") {
+                    let _ = window.show();
+                    let _ = window.set_focus();
+                }
             };
-        }
-    };
+            match event {
+                SystemTrayEvent::LeftClick { .. } => show_main(app),
+                SystemTrayEvent::MenuItemClick { id, .. } => match id.as_str() {
+                    "show" => show_main(app),
+                    "quit" => app.exit(0),
+                    _ => {}
+                },
+                _ => {}
+            }
+        })
+        .setup(|app| {
+            let window = app.get_window("main").unwrap();
 
-    let gen_cpp_dir = home_dir.join(".madola").join("gen_cpp");
-    println!("[Rust] Looking in: {:?}", gen_cpp_dir);
-    
-    // Create directory if it doesn't exist
-    if !gen_cpp_dir.exists() {
-        println!("[Rust] Directory does not exist, creating...");
-        if let Err(e) = fs::create_dir_all(&gen_cpp_dir) {
-            println!("[Rust] ERROR creating directory: {}", e);
-            return FileListResult {
-                success: false,
-                files: vec![],
-                error: Some(format!("Failed to create directory: {}", e)),
-            };
-        }
-    }
+            // Restore persisted appearance; the webview applies zoom/theme
+            // when it receives the event
+            let prefs = window_prefs_for(window.label());
+            let _ = emit_event(&window, AppEvent::ApplyWindowPrefs(prefs.clone()));
 
-    let mut files = Vec::new();
+            // Tell the frontend where the workspace lives
+            match paths::madola_paths() {
+                Ok(paths) => {
+                    let _ = emit_event(&window, AppEvent::MadolaPaths(paths));
+                }
+                Err(e) => println!("[Rust] ERROR resolving paths: {}", e),
+            }
 
-    match fs::read_dir(&gen_cpp_dir) {
-        Ok(entries) => {
-            for entry in entries.flatten() {
-                if let Ok(file_name) = entry.file_name().into_string() {
-                    if file_name.ends_with(".cpp") {
-                        if let Ok(metadata) = entry.metadata() {
-                            if let Ok(modified) = metadata.modified() {
-                                let modified_str = format!("{:?}", modified);
-                                println!("[Rust] Found C++ file: {} ({} bytes)", file_name, metadata.len());
-                                files.push(FileInfo {
-                                    name: file_name,
-                                    size: metadata.len(),
-                                    modified: modified_str,
-                                });
-                            }
+            // Reopen the last active file; a remembered file that no longer
+            // exists is skipped silently and the window starts empty
+            if let Some(path) = prefs.active_file {
+                if std::path::Path::new(&path).is_file() {
+                    let restore_window = window.clone();
+                    tauri::async_runtime::spawn(async move {
+                        let result = commands::files::open_file(path).await;
+                        if result.success {
+                            let _ = emit_event(&restore_window, AppEvent::RestoreFile(result));
                         }
-                    }
+                    });
                 }
             }
-        }
-        Err(e) => {
-            println!("[Rust] ERROR reading directory: {}", e);
-            return FileListResult {
-                success: false,
-                files: vec![],
-                error: Some(format!("Failed to read directory: {}", e)),
-            };
-        }
-    }
-
-    files.sort_by(|a, b| a.name.cmp(&b.name));
-    println!("[Rust] Returning {} C++ files", files.len());
-
-    FileListResult {
-        success: true,
-        files,
-        error: None,
-    }
-}
-
-// File browser: Get WASM modules from ~/.madola/trove
-#[tauri::command]
-async fn get_wasm_modules() -> ModuleListResult {
-    println!("[Rust] get_wasm_modules called");
-    
-    let home_dir = match dirs::home_dir() {
-        Some(dir) => {
-            println!("[Rust] Home dir: {:?}", dir);
-            dir
-        },
-        None => {
-            println!("[Rust] ERROR: Could not determine home directory");
-            return ModuleListResult {
-                success: false,
-                modules: vec![],
-                error: Some("Could not determine home directory".to_string()),
-            };
-        }
-    };
-
-    let trove_dir = home_dir.join(".madola").join("trove");
-    println!("[Rust] Looking in: {:?}", trove_dir);
-    
-    // Create directory if it doesn't exist
-    if !trove_dir.exists() {
-        println!("[Rust] Directory does not exist, creating...");
-        if let Err(e) = fs::create_dir_all(&trove_dir) {
-            println!("[Rust] ERROR creating directory: {}", e);
-            return ModuleListResult {
-                success: false,
-                modules: vec![],
-                error: Some(format!("Failed to create directory: {}", e)),
-            };
-        }
-    }
-
-    let mut modules = Vec::new();
 
-    match fs::read_dir(&trove_dir) {
-        Ok(entries) => {
-            for entry in entries.flatten() {
-                if let Ok(file_type) = entry.file_type() {
-                    if file_type.is_dir() {
-                        if let Ok(module_name) = entry.file_name().into_string() {
-                            println!("[Rust] Checking module directory: {}", module_name);
-                            let module_path = entry.path();
-                            let mut module_files = Vec::new();
-
-                            if let Ok(module_entries) = fs::read_dir(&module_path) {
-                                for file_entry in module_entries.flatten() {
-                                    if let Ok(file_name) = file_entry.file_name().into_string() {
-                                        if file_name.ends_with(".wasm") || file_name.ends_with(".js") {
-                                            if let Ok(metadata) = file_entry.metadata() {
-                                                if let Ok(modified) = metadata.modified() {
-                                                    let modified_str = format!("{:?}", modified);
-                                                    let file_type = if file_name.ends_with(".wasm") {
-                                                        "wasm"
-                                                    } else {
-                                                        "js"
-                                                    };
-
-                                                    println!("[Rust]   Found {} file: {} ({} bytes)", file_type, file_name, metadata.len());
-                                                    module_files.push(ModuleFile {
-                                                        name: file_name,
-                                                        file_type: file_type.to_string(),
-                                                        size: metadata.len(),
-                                                        modified: modified_str,
-                                                    });
-                                                }
-                                            }
-                                        }
-                                    }
+            // Handle file drop and close events
+            let main_window = window.clone();
+            window.on_window_event(move |event| {
+                match event {
+                    WindowEvent::FileDrop(tauri::FileDropEvent::Dropped(paths)) => {
+                        let settings = load_settings();
+                        if settings.auto_import_on_drop {
+                            match madola_base() {
+                                Ok(base) => {
+                                    let results = import_dropped_files(
+                                        &base,
+                                        &settings.drop_import_module,
+                                        paths,
+                                    );
+                                    let _ = emit_event(&main_window, AppEvent::FilesImported(results));
+                                }
+                                Err(e) => {
+                                    println!("[Rust] ERROR importing dropped files: {}", e)
                                 }
                             }
-
-                            if !module_files.is_empty() {
-                                println!("[Rust] Added module '{}' with {} files", module_name, module_files.len());
-                                modules.push(WasmModule {
-                                    name: module_name,
-                                    files: module_files,
-                                });
-                            } else {
-                                println!("[Rust] Module '{}' has no .wasm or .js files, skipping", module_name);
+                        } else {
+                            // Auto-import disabled: stat each path so the
+                            // frontend can decide per entry without another
+                            // round-trip
+                            if let Some(path) = paths.first() {
+                                println!("File dropped: {:?}", path);
                             }
+                            let described = commands::files::describe_dropped_paths(paths);
+                            let _ = emit_event(&main_window, AppEvent::FileDropped(described));
                         }
                     }
-                }
-            }
-        }
-        Err(e) => {
-            println!("[Rust] ERROR reading directory: {}", e);
-            return ModuleListResult {
-                success: false,
-                modules: vec![],
-                error: Some(format!("Failed to read directory: {}", e)),
-            };
-        }
-    }
-
-    modules.sort_by(|a, b| a.name.cmp(&b.name));
-    println!("[Rust] Returning {} WASM modules", modules.len());
-
-    ModuleListResult {
-        success: true,
-        modules,
-        error: None,
-    }
-}
-
-// File browser: Get C++ file content
-#[tauri::command]
-async fn get_cpp_file_content(filename: String) -> FileContentResult {
-    let home_dir = match dirs::home_dir() {
-        Some(dir) => dir,
-        None => return FileContentResult {
-            success: false,
-            content: None,
-            filename: None,
-            error: Some("Could not determine home directory".to_string()),
-        },
-    };
-
-    let file_path = home_dir.join(".madola").join("gen_cpp").join(&filename);
-    
-    if !file_path.exists() {
-        return FileContentResult {
-            success: false,
-            content: None,
-            filename: None,
-            error: Some("File not found".to_string()),
-        };
-    }
-
-    match fs::read_to_string(&file_path) {
-        Ok(content) => FileContentResult {
-            success: true,
-            content: Some(content),
-            filename: Some(filename),
-            error: None,
-        },
-        Err(e) => FileContentResult {
-            success: false,
-            content: None,
-            filename: None,
-            error: Some(format!("Failed to read file: {}", e)),
-        },
-    }
-}
-
-fn main() {
-    tauri::Builder::default()
-        .invoke_handler(tauri::generate_handler![
-            open_file,
-            save_file,
-            get_file_name,
-            set_title,
-            get_cpp_files,
-            get_wasm_modules,
-            get_cpp_file_content
-        ])
-        .setup(|app| {
-            let window = app.get_window("main").unwrap();
-
-            // Handle file drop events
-            window.on_window_event(|event| {
-                if let WindowEvent::FileDrop(tauri::FileDropEvent::Dropped(paths)) = event {
-                    // Handle dropped files
-                    if let Some(path) = paths.first() {
-                        println!("File dropped: {:?}", path);
-                        // You can emit an event to the frontend here
+                    WindowEvent::CloseRequested { api, .. } => {
+                        // A dirty window gets a save/discard/cancel prompt
+                        // before anything closes
+                        if main_window
+                            .state::<DirtyWindows>()
+                            .is_dirty(main_window.label())
+                        {
+                            api.prevent_close();
+                            let _ = emit_event(&main_window, AppEvent::ConfirmClose);
+                        } else if load_settings().minimize_to_tray {
+                            // Hide to the tray instead of quitting when enabled
+                            api.prevent_close();
+                            let _ = main_window.hide();
+                        }
                     }
+                    _ => {}
                 }
             });
 
             Ok(())
         })
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
-}
\ No newline at end of file
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app, event| match event {
+            // Flush on both: which one fires first depends on whether the
+            // quit came from the tray, the window, or the OS
+            tauri::RunEvent::ExitRequested { .. } | tauri::RunEvent::Exit => {
+                commands::flush_before_exit(app);
+            }
+            _ => {}
+        });
+}
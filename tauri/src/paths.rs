@@ -0,0 +1,300 @@
+//! Base-directory resolution and path validation shared by the command
+//! modules.
+
+use std::path::{Path, PathBuf};
+
+use crate::types::MadolaPaths;
+
+// Resolve the ~/.madola base directory
+// Where the .madola workspace lives. Sandboxed environments (Flatpak, CI)
+// may have no home directory, so fall back through a chain instead of
+// failing every file command: MADOLA_HOME, then ~/.madola, then
+// $XDG_DATA_HOME/madola, then a temp-dir location. Resolved once and
+// logged so triage can see which source won.
+pub fn madola_base() -> Result<PathBuf, String> {
+    Ok(resolved_base().0.clone())
+}
+
+// Which fallback won the base-directory resolution, for diagnostics
+pub fn madola_base_source() -> &'static str {
+    resolved_base().1
+}
+
+fn resolved_base() -> &'static (PathBuf, &'static str) {
+    use std::sync::OnceLock;
+    static BASE: OnceLock<(PathBuf, &'static str)> = OnceLock::new();
+
+    BASE.get_or_init(|| {
+        let (base, source) = if let Some(dir) = std::env::var_os("MADOLA_HOME") {
+            (PathBuf::from(dir), "MADOLA_HOME")
+        } else if let Some(home) = dirs::home_dir() {
+            (home.join(".madola"), "home directory")
+        } else if let Some(data) = std::env::var_os("XDG_DATA_HOME") {
+            (PathBuf::from(data).join("madola"), "XDG_DATA_HOME")
+        } else {
+            (std::env::temp_dir().join("madola"), "temp directory")
+        };
+        println!("[Rust] Using {:?} as the MADOLA base (from {})", base, source);
+        (base, source)
+    })
+}
+
+// The resolved directory layout, for the "madola-paths" event
+pub fn madola_paths() -> Result<MadolaPaths, String> {
+    let base = madola_base()?;
+    Ok(MadolaPaths {
+        base: base.to_string_lossy().to_string(),
+        gen_cpp: base.join("gen_cpp").to_string_lossy().to_string(),
+        trove: base.join("trove").to_string_lossy().to_string(),
+    })
+}
+
+pub fn settings_path() -> Result<PathBuf, String> {
+    Ok(madola_base()?.join("settings.json"))
+}
+
+pub fn window_prefs_path() -> Result<PathBuf, String> {
+    Ok(madola_base()?.join("window_prefs.json"))
+}
+
+pub fn projects_path() -> Result<PathBuf, String> {
+    Ok(madola_base()?.join("projects.json"))
+}
+
+// Expand a leading `~` to the home directory; other paths pass through
+pub fn expand_tilde(path: &str) -> Result<PathBuf, String> {
+    if path == "~" {
+        return dirs::home_dir().ok_or_else(|| "Could not determine home directory".to_string());
+    }
+    if let Some(rest) = path.strip_prefix("~/").or_else(|| path.strip_prefix("~\\")) {
+        return dirs::home_dir()
+            .map(|home| home.join(rest))
+            .ok_or_else(|| "Could not determine home directory".to_string());
+    }
+    Ok(PathBuf::from(path))
+}
+
+// Resolve whatever string the webview sent into an absolute path to an
+// existing file: expand `~`, then canonicalize so `.`/`..` and relative
+// paths behave the same on every platform
+pub fn resolve_existing_path(path: &str) -> Result<PathBuf, String> {
+    expand_tilde(path)?
+        .canonicalize()
+        .map_err(|e| format!("Failed to resolve path {}: {}", path, e))
+}
+
+// Same, for a file that may not exist yet: canonicalize the parent
+// directory and re-attach the final component
+pub fn resolve_target_path(path: &str) -> Result<PathBuf, String> {
+    let expanded = expand_tilde(path)?;
+    let file_name = expanded
+        .file_name()
+        .ok_or_else(|| format!("Path has no file name: {}", path))?
+        .to_os_string();
+    let parent = match expanded.parent() {
+        Some(p) if !p.as_os_str().is_empty() => p.to_path_buf(),
+        _ => PathBuf::from("."),
+    };
+    let parent = parent
+        .canonicalize()
+        .map_err(|e| format!("Failed to resolve directory {:?}: {}", parent, e))?;
+    Ok(parent.join(file_name))
+}
+
+// Validate a gen_cpp-relative path: subdirectories are fine, but absolute
+// paths and `..` traversal are not
+pub fn validate_relative_cpp_path(path: &str) -> Result<(), String> {
+    if path.is_empty() {
+        return Err("Path must not be empty".to_string());
+    }
+    let p = Path::new(path);
+    if p.is_absolute() || path.starts_with('\\') {
+        return Err("Path must be relative to gen_cpp".to_string());
+    }
+    for component in p.components() {
+        match component {
+            std::path::Component::Normal(_) => {}
+            _ => return Err("Path must stay within gen_cpp".to_string()),
+        }
+    }
+    Ok(())
+}
+
+// Resolve a gen_cpp-relative path and verify the canonicalized result
+// still lives under gen_cpp. The component check rejects textual traversal
+// (`..`, absolute paths); canonicalizing afterwards also stops symlinks
+// from smuggling reads outside the directory.
+pub fn resolve_in_gen_cpp(gen_cpp_dir: &Path, relative: &str) -> Result<PathBuf, String> {
+    resolve_within(gen_cpp_dir, relative, "gen_cpp")
+}
+
+// Same confinement for a user-chosen external directory
+pub fn resolve_external(dir: &Path, relative: &str) -> Result<PathBuf, String> {
+    resolve_within(dir, relative, "the browsed directory")
+}
+
+// Resolve `relative` inside `root`, rejecting traversal, symlink escapes,
+// and directories; `label` names the root in error messages
+fn resolve_within(root: &Path, relative: &str, label: &str) -> Result<PathBuf, String> {
+    validate_relative_cpp_path(relative)?;
+    let joined = root.join(relative);
+    if !joined.exists() {
+        return Err("File not found".to_string());
+    }
+    let canonical = joined
+        .canonicalize()
+        .map_err(|e| format!("Failed to resolve path: {}", e))?;
+    let root = root
+        .canonicalize()
+        .map_err(|e| format!("Failed to resolve path: {}", e))?;
+    if !canonical.starts_with(&root) {
+        return Err(format!("Path must stay within {}", label));
+    }
+    if canonical.is_dir() {
+        // Every caller reads or hashes the result, so a subdirectory is an
+        // error here, not at read time
+        return Err("path is a directory, not a file".to_string());
+    }
+    Ok(canonical)
+}
+
+// Validate that a gen_cpp filename is a single .cpp (or .cpp.gz) path
+// component
+pub fn validate_cpp_filename(filename: &str) -> Result<(), String> {
+    if filename.is_empty() || !(filename.ends_with(".cpp") || filename.ends_with(".cpp.gz")) {
+        return Err("Filename must end with .cpp or .cpp.gz".to_string());
+    }
+    if filename.contains('/') || filename.contains('\\') || filename.contains("..") {
+        return Err("Filename must be a single path component".to_string());
+    }
+    Ok(())
+}
+
+// A module name becomes a directory under trove, so it must be a single
+// normal path component
+pub fn validate_module_name(name: &str) -> Result<(), String> {
+    if name.is_empty() {
+        return Err("Module name must not be empty".to_string());
+    }
+    if name == "." || name == ".." || name.contains('/') || name.contains('\\') {
+        return Err(format!("Invalid module name: {}", name));
+    }
+    Ok(())
+}
+
+// Shared scratch-directory helper for the per-module test suites
+#[cfg(test)]
+pub fn temp_dir(tag: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!(
+        "madola-test-{}-{}",
+        tag,
+        std::process::id()
+    ));
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn resolved_paths_share_the_base_directory() {
+        let paths = madola_paths().unwrap();
+        assert!(paths.gen_cpp.starts_with(&paths.base));
+        assert!(paths.gen_cpp.ends_with("gen_cpp"));
+        assert!(paths.trove.ends_with("trove"));
+    }
+
+    #[test]
+    fn relative_cpp_paths_reject_traversal() {
+        assert!(validate_relative_cpp_path("file.cpp").is_ok());
+        assert!(validate_relative_cpp_path("sub/dir/file.cpp").is_ok());
+        assert!(validate_relative_cpp_path("../escape.cpp").is_err());
+        assert!(validate_relative_cpp_path("sub/../../escape.cpp").is_err());
+        assert!(validate_relative_cpp_path("/etc/passwd").is_err());
+        assert!(validate_relative_cpp_path("").is_err());
+    }
+
+    #[test]
+    fn validate_cpp_filename_rejects_traversal() {
+        assert!(validate_cpp_filename("ok.cpp").is_ok());
+        assert!(validate_cpp_filename("../evil.cpp").is_err());
+        assert!(validate_cpp_filename("sub/dir.cpp").is_err());
+        assert!(validate_cpp_filename("not_cpp.txt").is_err());
+        assert!(validate_cpp_filename("").is_err());
+    }
+
+    #[test]
+    fn path_resolution_expands_tilde_and_dotdot() {
+        let dir = temp_dir("resolve");
+        fs::create_dir_all(dir.join("sub")).unwrap();
+        fs::write(dir.join("a.cpp"), "x").unwrap();
+        let canonical = dir.canonicalize().unwrap();
+
+        let raw = format!("{}/sub/../a.cpp", dir.display());
+        assert_eq!(
+            resolve_existing_path(&raw).unwrap(),
+            canonical.join("a.cpp")
+        );
+        assert!(resolve_existing_path(&format!("{}/missing.cpp", dir.display())).is_err());
+
+        // The target itself need not exist, but its parent must
+        let target = resolve_target_path(&format!("{}/sub/../new.cpp", dir.display())).unwrap();
+        assert_eq!(target, canonical.join("new.cpp"));
+        assert!(resolve_target_path(&format!("{}/nodir/new.cpp", dir.display())).is_err());
+
+        let home = dirs::home_dir().unwrap();
+        assert_eq!(expand_tilde("~/x.cpp").unwrap(), home.join("x.cpp"));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn external_resolution_is_confined_to_the_browsed_directory() {
+        let dir = temp_dir("external");
+        let browsed = dir.join("checkout");
+        fs::create_dir_all(&browsed).unwrap();
+        fs::write(browsed.join("ok.cpp"), "int main() {}").unwrap();
+        fs::write(dir.join("secret.cpp"), "int main() {}").unwrap();
+
+        assert!(resolve_external(&browsed, "ok.cpp").is_ok());
+        assert!(resolve_external(&browsed, "../secret.cpp").is_err());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn gen_cpp_containment_allows_nested_paths_and_blocks_escapes() {
+        let dir = temp_dir("containment");
+        let gen_cpp = dir.join("gen_cpp");
+        fs::create_dir_all(gen_cpp.join("sub")).unwrap();
+        fs::write(gen_cpp.join("sub").join("nested.cpp"), "x").unwrap();
+        fs::write(dir.join("outside.cpp"), "y").unwrap();
+
+        let resolved = resolve_in_gen_cpp(&gen_cpp, "sub/nested.cpp").unwrap();
+        assert!(resolved.ends_with("nested.cpp"));
+
+        // Textual traversal is rejected before touching the filesystem
+        assert!(resolve_in_gen_cpp(&gen_cpp, "../outside.cpp").is_err());
+        assert!(resolve_in_gen_cpp(&gen_cpp, "/etc/passwd").is_err());
+
+        // A subdirectory resolves but is not openable
+        assert_eq!(
+            resolve_in_gen_cpp(&gen_cpp, "sub").unwrap_err(),
+            "path is a directory, not a file"
+        );
+
+        // A symlink pointing out of gen_cpp is caught by canonicalization
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(dir.join("outside.cpp"), gen_cpp.join("sneaky.cpp"))
+                .unwrap();
+            let err = resolve_in_gen_cpp(&gen_cpp, "sneaky.cpp").unwrap_err();
+            assert_eq!(err, "Path must stay within gen_cpp");
+        }
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,556 @@
+//! Serializable settings and result types shared across the command
+//! modules, plus the sort keys the listing commands accept.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+
+use crate::paths::{settings_path, window_prefs_path};
+
+// Persisted application settings, stored at ~/.madola/settings.json.
+// Unknown fields are ignored and missing fields fall back to defaults so old
+// settings files keep working.
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct Settings {
+    pub command_timeout_secs: u64,
+    // Extensions (without the dot) that the C++ file browser lists
+    pub cpp_extensions: Vec<String>,
+    // When enabled, closing the window hides it to the tray instead of quitting
+    pub minimize_to_tray: bool,
+    // Largest file the read commands will load into memory
+    pub max_file_size_bytes: u64,
+    // Path to a C++-to-WASM compiler (e.g. emcc); compile_to_wasm refuses
+    // to run while this is unset
+    pub compiler_path: Option<String>,
+    // When enabled, dropped .cpp/.wasm/.js files are copied into the
+    // workspace instead of just being reported to the frontend
+    pub auto_import_on_drop: bool,
+    // Trove module that receives dropped .wasm/.js files
+    pub drop_import_module: String,
+    // Force the polling fallback for directory watching even when the
+    // native notify backend works. Polling trades CPU (a periodic scan of
+    // gen_cpp and trove) and latency (changes surface up to one interval
+    // late) for reliability on network filesystems and containers where
+    // native events are silently dropped.
+    pub poll_watch: bool,
+    pub poll_interval_secs: u64,
+    // External directories browsed via list_external_cpp, most recent first
+    pub recent_external_dirs: Vec<String>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            command_timeout_secs: 10,
+            cpp_extensions: vec!["cpp".to_string()],
+            minimize_to_tray: false,
+            max_file_size_bytes: 25 * 1024 * 1024,
+            compiler_path: None,
+            auto_import_on_drop: false,
+            drop_import_module: "imported".to_string(),
+            poll_watch: false,
+            poll_interval_secs: 5,
+            recent_external_dirs: vec![],
+        }
+    }
+}
+
+pub fn load_settings() -> Settings {
+    settings_path()
+        .ok()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+pub fn save_settings(settings: &Settings) -> Result<(), String> {
+    let path = settings_path()?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create directory: {}", e))?;
+    }
+    let content = serde_json::to_string_pretty(settings)
+        .map_err(|e| format!("Failed to serialize settings: {}", e))?;
+    fs::write(&path, content).map_err(|e| format!("Failed to write settings: {}", e))
+}
+
+// Version and environment details for the About dialog and bug reports
+#[derive(Serialize)]
+pub struct AppInfo {
+    pub app_version: String,
+    pub tauri_version: String,
+    pub os: String,
+    pub arch: String,
+    pub madola_home: String,
+    // True when the MADOLA_HOME environment variable overrides the default
+    // ~/.madola location
+    pub madola_home_overridden: bool,
+}
+
+// Per-window appearance preferences, persisted across launches in
+// ~/.madola/window_prefs.json keyed by window label
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct WindowPrefs {
+    pub zoom: f64,
+    pub theme: String,
+    // Absolute path of the file the window last had open, restored on launch
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub active_file: Option<String>,
+}
+
+impl Default for WindowPrefs {
+    fn default() -> Self {
+        WindowPrefs {
+            zoom: 1.0,
+            theme: "system".to_string(),
+            active_file: None,
+        }
+    }
+}
+
+pub const MIN_ZOOM: f64 = 0.5;
+pub const MAX_ZOOM: f64 = 3.0;
+
+// Clamp whatever arrived (from the frontend or a hand-edited prefs file)
+// into the supported range
+pub fn sanitize_window_prefs(prefs: WindowPrefs) -> WindowPrefs {
+    WindowPrefs {
+        zoom: prefs.zoom.clamp(MIN_ZOOM, MAX_ZOOM),
+        ..prefs
+    }
+}
+
+// An appearance-only save carries no active_file; merge the stored value
+// forward so saving the theme doesn't forget the open file
+pub fn merge_window_prefs(existing: Option<&WindowPrefs>, incoming: WindowPrefs) -> WindowPrefs {
+    let mut prefs = sanitize_window_prefs(incoming);
+    if prefs.active_file.is_none() {
+        prefs.active_file = existing.and_then(|p| p.active_file.clone());
+    }
+    prefs
+}
+
+pub fn load_window_prefs() -> HashMap<String, WindowPrefs> {
+    window_prefs_path()
+        .ok()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+pub fn window_prefs_for(label: &str) -> WindowPrefs {
+    sanitize_window_prefs(load_window_prefs().remove(label).unwrap_or_default())
+}
+
+// How listing commands order their results. Natural compares digit runs by
+// numeric value (File2 before File10) with a case-insensitive tiebreak;
+// raw is the old plain codepoint order. Size (largest first) and modified
+// (newest first) use the scanned metadata, with natural name order as the
+// tiebreak.
+#[derive(Deserialize, Clone, Copy, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum SortKey {
+    #[default]
+    Natural,
+    Raw,
+    Size,
+    Modified,
+}
+
+// Locale-independent natural comparison: digit runs compare by value,
+// everything else compares case-insensitively, with the raw order as the
+// final tiebreak so the result stays deterministic
+pub fn natural_cmp(a: &str, b: &str) -> std::cmp::Ordering {
+    use std::cmp::Ordering;
+
+    let mut a_chars = a.chars().peekable();
+    let mut b_chars = b.chars().peekable();
+    loop {
+        match (a_chars.peek().copied(), b_chars.peek().copied()) {
+            (None, None) => break,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) => {
+                if x.is_ascii_digit() && y.is_ascii_digit() {
+                    let mut a_run = String::new();
+                    while let Some(&c) = a_chars.peek() {
+                        if !c.is_ascii_digit() {
+                            break;
+                        }
+                        a_run.push(c);
+                        a_chars.next();
+                    }
+                    let mut b_run = String::new();
+                    while let Some(&c) = b_chars.peek() {
+                        if !c.is_ascii_digit() {
+                            break;
+                        }
+                        b_run.push(c);
+                        b_chars.next();
+                    }
+                    // Compare by value without parsing: strip leading zeros,
+                    // then longer means larger
+                    let a_digits = a_run.trim_start_matches('0');
+                    let b_digits = b_run.trim_start_matches('0');
+                    let ord = a_digits
+                        .len()
+                        .cmp(&b_digits.len())
+                        .then_with(|| a_digits.cmp(b_digits));
+                    if ord != Ordering::Equal {
+                        return ord;
+                    }
+                } else {
+                    let ord = x.to_lowercase().cmp(y.to_lowercase());
+                    if ord != Ordering::Equal {
+                        return ord;
+                    }
+                    a_chars.next();
+                    b_chars.next();
+                }
+            }
+        }
+    }
+    a.cmp(b)
+}
+
+pub fn sort_key_cmp(sort: SortKey, a: &str, b: &str) -> std::cmp::Ordering {
+    match sort {
+        SortKey::Raw => a.cmp(b),
+        _ => natural_cmp(a, b),
+    }
+}
+
+// ISO 8601 (RFC 3339) rendering of a filesystem timestamp, in UTC
+pub fn iso8601(time: std::time::SystemTime) -> String {
+    chrono::DateTime::<chrono::Utc>::from(time).to_rfc3339()
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct FileInfo {
+    pub name: String,
+    // Absolute path, so the frontend can hand it straight to open_file
+    pub path: String,
+    pub size: u64,
+    pub modified: String,
+    pub extension: String,
+    // True for gzip-compressed sources like foo.cpp.gz
+    pub compressed: bool,
+    // Path relative to gen_cpp; equals `name` for top-level files
+    pub relative_path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hash: Option<String>,
+    #[serde(skip)]
+    pub modified_at: Option<std::time::SystemTime>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct FileListResult {
+    pub success: bool,
+    pub files: Vec<FileInfo>,
+    // Whether gen_cpp already existed before this scan, and whether this
+    // call created it: a fresh directory is genuinely empty, not a scan of
+    // a broken or placeholder mount
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exists: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub created: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub skipped: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ModuleFile {
+    pub name: String,
+    // Absolute path, so the frontend can hand it straight to open_file
+    pub path: String,
+    #[serde(rename = "type")]
+    pub file_type: String,
+    pub size: u64,
+    pub modified: String,
+    // True for a .js file that doesn't look like wasm glue (see
+    // js_is_standalone); always false for .wasm files
+    pub suspicious: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct WasmModule {
+    pub name: String,
+    pub files: Vec<ModuleFile>,
+    // Aggregates over `files`, computed during the scan so the frontend can
+    // sort without summing
+    pub total_size: u64,
+    // ISO 8601 timestamp of the newest file in the module
+    pub latest_modified: String,
+    #[serde(skip)]
+    pub latest_modified_at: Option<std::time::SystemTime>,
+    // "ready" | "empty" | "error" — lets the UI distinguish a module with
+    // no artifacts yet from one it could not read
+    pub status: String,
+}
+
+// Quick look at a .wasm without reading the whole file: header check,
+// binary-format version, and top-level section names
+#[derive(Serialize, Deserialize, Clone)]
+pub struct WasmHeader {
+    pub magic_ok: bool,
+    pub version: u32,
+    // Standard sections by kind ("type", "code", ...); custom sections by
+    // their own name ("name", "producers", ...)
+    pub section_names: Vec<String>,
+}
+
+// One-click diagnostics for the directories every file command depends on
+#[derive(Serialize, Deserialize, Clone)]
+pub struct HealthReport {
+    pub base_writable: bool,
+    pub gen_cpp_ok: bool,
+    pub trove_ok: bool,
+    // Which fallback resolved the base directory (e.g. "MADOLA_HOME")
+    pub home_source: String,
+    pub errors: Vec<String>,
+}
+
+// What the frontend gets for each path in a file drop, so it can decide
+// per entry (expand directories, filter by extension) without a round-trip
+#[derive(Serialize, Deserialize, Clone)]
+pub struct DroppedPath {
+    pub path: String,
+    pub is_dir: bool,
+    pub size: u64,
+    // Lowercased extension without the dot; empty for directories and
+    // extensionless files
+    pub ext: String,
+    // False when the path vanished between the drop and the stat
+    pub exists: bool,
+}
+
+// Result of a destructive bulk command. With `dry_run` echoed true, the
+// listed paths were only collected -- nothing was touched on disk.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct DeleteResult {
+    pub success: bool,
+    pub dry_run: bool,
+    pub deleted: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+// One entry of the heuristic C++ outline; `kind` is "class", "struct",
+// or "function"
+#[derive(Serialize, Deserialize, Clone)]
+pub struct OutlineItem {
+    pub kind: String,
+    pub name: String,
+    pub line: u32,
+}
+
+// A named grouping of gen_cpp files from ~/.madola/projects.json
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Project {
+    pub name: String,
+    pub files: Vec<String>,
+    // Manifest entries whose files no longer exist on disk
+    pub stale: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ModuleListResult {
+    pub success: bool,
+    pub modules: Vec<WasmModule>,
+    // Per-module problems (e.g. one unreadable directory) that shouldn't
+    // hide the rest of the scan
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct DiskSpace {
+    pub total_bytes: u64,
+    pub available_bytes: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct FileContentResult {
+    pub success: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub filename: Option<String>,
+    // True when the file started with a UTF-8 BOM (stripped from `content`);
+    // pass add_bom on save to restore it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub has_bom: Option<bool>,
+    // "lf", "crlf", or "mixed"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line_ending: Option<String>,
+    // Counts over the (BOM-stripped) content, so the editor status bar
+    // doesn't need a second pass over the text
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line_count: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub char_count: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct SaveResult {
+    pub success: bool,
+    pub bytes_written: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+// One chunk of a streamed file
+#[derive(Serialize, Clone)]
+pub struct FileChunk {
+    pub seq: u32,
+    pub data: String,
+    pub eof: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct DiffLine {
+    pub tag: String,
+    pub text: String,
+}
+
+// Result of importing one dropped file
+#[derive(Serialize, Clone)]
+pub struct ImportedFile {
+    pub source: String,
+    pub target: String,
+    pub success: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Serialize, Clone)]
+pub struct ProgressPayload {
+    pub done: usize,
+    pub total: usize,
+    pub current: String,
+}
+
+// One line of compiler output streamed to the frontend during compile_to_wasm
+#[derive(Serialize, Clone)]
+pub struct CompileOutput {
+    pub stream: String,
+    pub line: String,
+}
+
+// Resolved workspace directories, pushed to the frontend as the
+// "madola-paths" event so it never hardcodes ~/.madola
+#[derive(Serialize, Clone)]
+pub struct MadolaPaths {
+    pub base: String,
+    pub gen_cpp: String,
+    pub trove: String,
+}
+
+// One entry in the template picker, either built in or user supplied
+#[derive(Serialize)]
+pub struct TemplateInfo {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+}
+
+// One row of the per-extension breakdown shown on the project overview
+#[derive(Serialize)]
+pub struct ExtStat {
+    pub ext: String,
+    pub count: usize,
+    pub total_bytes: u64,
+}
+
+// Outcome of checking a module against its manifest.json
+#[derive(Serialize)]
+pub struct VerifyResult {
+    pub ok: bool,
+    // Listed in the manifest but not on disk
+    pub missing: Vec<String>,
+    // Present but hashing to something else
+    pub mismatched: Vec<String>,
+    // On disk but not in the manifest (manifest.json itself excluded)
+    pub extra: Vec<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn window_prefs_clamp_zoom_and_default_sensibly() {
+        let prefs = sanitize_window_prefs(WindowPrefs {
+            zoom: 10.0,
+            theme: "dark".to_string(),
+            ..WindowPrefs::default()
+        });
+        assert_eq!(prefs.zoom, MAX_ZOOM);
+        assert_eq!(prefs.theme, "dark");
+        assert_eq!(
+            sanitize_window_prefs(WindowPrefs {
+                zoom: 0.1,
+                ..WindowPrefs::default()
+            })
+            .zoom,
+            MIN_ZOOM
+        );
+
+        // Missing fields in a hand-edited file fall back to defaults
+        let parsed: WindowPrefs = serde_json::from_str("{}").unwrap();
+        assert_eq!(parsed.zoom, 1.0);
+        assert_eq!(parsed.theme, "system");
+        assert_eq!(parsed.active_file, None);
+    }
+
+    #[test]
+    fn appearance_saves_keep_the_remembered_active_file() {
+        let stored = WindowPrefs {
+            active_file: Some("/work/a.cpp".to_string()),
+            ..WindowPrefs::default()
+        };
+
+        // No active_file in the incoming save: the stored one survives
+        let merged = merge_window_prefs(Some(&stored), WindowPrefs::default());
+        assert_eq!(merged.active_file.as_deref(), Some("/work/a.cpp"));
+
+        // An explicit value wins over the stored one
+        let merged = merge_window_prefs(
+            Some(&stored),
+            WindowPrefs {
+                active_file: Some("/work/b.cpp".to_string()),
+                ..WindowPrefs::default()
+            },
+        );
+        assert_eq!(merged.active_file.as_deref(), Some("/work/b.cpp"));
+
+        // Nothing stored, nothing incoming
+        assert_eq!(merge_window_prefs(None, WindowPrefs::default()).active_file, None);
+    }
+
+    #[test]
+    fn natural_sort_orders_numbers_by_value() {
+        use std::cmp::Ordering;
+
+        assert_eq!(natural_cmp("File2.cpp", "File10.cpp"), Ordering::Less);
+        assert_eq!(natural_cmp("file10.cpp", "File2.cpp"), Ordering::Greater);
+        // Case-insensitive primary order
+        assert_eq!(natural_cmp("alpha.cpp", "Beta.cpp"), Ordering::Less);
+        // Case only differs: the raw order is the deterministic tiebreak
+        assert_eq!(natural_cmp("A.cpp", "a.cpp"), Ordering::Less);
+        // Same numeric value with leading zeros is still deterministic
+        assert_eq!(natural_cmp("f007.cpp", "f7.cpp"), Ordering::Less);
+        // Raw keeps the old codepoint behavior for callers that want it
+        assert_eq!(
+            sort_key_cmp(SortKey::Raw, "File10.cpp", "File2.cpp"),
+            Ordering::Less
+        );
+    }
+}